@@ -30,6 +30,48 @@ mod multisig;
 #[cfg(feature = "multisig")]
 pub use multisig::{ClsagDetails, ClsagAddendum, ClsagMultisig};
 
+/// A cache of `hash_to_point` results, keyed by the compressed ring-member key they were computed
+/// from.
+///
+/// The same decoy frequently recurs across the CLSAGs within a block, and `hash_to_point` is one
+/// of the more expensive operations `core` performs per ring member, so sharing this cache across
+/// many verifications (as `verify_batch` does) avoids redundant work.
+#[cfg(feature = "multithread")]
+pub struct HashToPointCache(std::sync::RwLock<std::collections::HashMap<[u8; 32], EdwardsPoint>>);
+#[cfg(feature = "multithread")]
+impl HashToPointCache {
+  /// Create a new, empty cache.
+  pub fn new() -> Self {
+    Self(std::sync::RwLock::new(std::collections::HashMap::new()))
+  }
+
+  fn hash_to_point(&self, key: [u8; 32]) -> EdwardsPoint {
+    if let Some(point) = self.0.read().unwrap().get(&key) {
+      return *point;
+    }
+    let point = hash_to_point(key);
+    self.0.write().unwrap().insert(key, point);
+    point
+  }
+}
+#[cfg(feature = "multithread")]
+impl Default for HashToPointCache {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// A no-op stand-in for `HashToPointCache` when the `multithread` feature isn't enabled, so `core`
+/// doesn't need two distinct signatures depending on the feature set.
+#[cfg(not(feature = "multithread"))]
+pub struct HashToPointCache;
+#[cfg(not(feature = "multithread"))]
+impl HashToPointCache {
+  fn hash_to_point(&self, key: [u8; 32]) -> EdwardsPoint {
+    hash_to_point(key)
+  }
+}
+
 /// Errors when working with CLSAGs.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "std", derive(thiserror::Error))]
@@ -55,6 +97,32 @@ pub enum ClsagError {
   /// The `c1` variable was invalid.
   #[cfg_attr(feature = "std", error("invalid c1"))]
   InvalidC1,
+  /// A scalar (a member of `s`, or `c1`) was not canonically encoded (reduced mod l).
+  #[cfg_attr(feature = "std", error("non-canonical scalar"))]
+  NonCanonicalScalar,
+  /// A point (`D`) was not canonically encoded (its compressed bytes weren't the unique
+  /// compressed encoding of the point they decompress to).
+  #[cfg_attr(feature = "std", error("non-canonical point"))]
+  NonCanonicalPoint,
+}
+
+fn read_canonical_scalar<R: Read>(r: &mut R) -> io::Result<Scalar> {
+  let mut bytes = [0; 32];
+  r.read_exact(&mut bytes)?;
+  Option::from(Scalar::from_canonical_bytes(bytes))
+    .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "non-canonical scalar"))
+}
+
+fn read_canonical_point<R: Read>(r: &mut R) -> io::Result<EdwardsPoint> {
+  let mut bytes = [0; 32];
+  r.read_exact(&mut bytes)?;
+  let point = curve25519_dalek::edwards::CompressedEdwardsY(bytes)
+    .decompress()
+    .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "invalid point"))?;
+  if point.compress().to_bytes() != bytes {
+    Err(io::Error::new(io::ErrorKind::Other, "non-canonical point"))?;
+  }
+  Ok(point)
 }
 
 /// Context on the ring member being signed for.
@@ -102,6 +170,7 @@ fn core(
   D: &EdwardsPoint,
   s: &[Scalar],
   A_c1: &Mode,
+  hash_to_point_cache: Option<&HashToPointCache>,
 ) -> ((EdwardsPoint, Scalar, Scalar), Scalar) {
   let n = ring.len();
 
@@ -192,7 +261,10 @@ fn core(
       }
     };
 
-    let PH = hash_to_point(P[i].compress().0);
+    let PH = match hash_to_point_cache {
+      Some(cache) => cache.hash_to_point(P[i].compress().0),
+      None => hash_to_point(P[i].compress().0),
+    };
 
     // (c_p * I) + (c_c * D) + (s_i * PH)
     let R = match A_c1 {
@@ -256,7 +328,7 @@ impl Clsag {
       s.push(Scalar::random(rng));
     }
     let ((D, c_p, c_c), c1) =
-      core(input.decoys.ring(), I, &pseudo_out, msg, &D, &s, &Mode::Sign(r, A, AH));
+      core(input.decoys.ring(), I, &pseudo_out, msg, &D, &s, &Mode::Sign(r, A, AH), None);
 
     ClsagSignCore {
       incomplete_clsag: Clsag { D, s, c1 },
@@ -324,6 +396,11 @@ impl Clsag {
   }
 
   /// Verify the CLSAG signature against the given Transaction data.
+  ///
+  /// This does not check `s`, `c1`, or `D` are canonically encoded (reduced mod l, and a unique
+  /// point encoding respectively). A `Clsag` built via `read_canonical` is guaranteed to not have
+  /// a second, distinct byte encoding which would also pass this check. `verify_canonical` only
+  /// re-checks the scalars, not `D`.
   pub fn verify(
     &self,
     ring: &[[EdwardsPoint; 2]],
@@ -331,8 +408,42 @@ impl Clsag {
     pseudo_out: &EdwardsPoint,
     msg: &[u8; 32],
   ) -> Result<(), ClsagError> {
-    // Preliminary checks. s, c1, and points must also be encoded canonically, which isn't checked
-    // here
+    self.verify_with_cache(ring, I, pseudo_out, msg, None)
+  }
+
+  /// Verify this CLSAG's `s` scalars and `c1` are canonically encoded (reduced mod l), then verify
+  /// it against the given Transaction data.
+  ///
+  /// `D`'s canonicity can't be re-checked here as, once decompressed into an `EdwardsPoint`, the
+  /// original compressed bytes are no longer available to compare against; use `read_canonical` to
+  /// enforce that at decode time instead, before a `Clsag` is ever constructed.
+  pub fn verify_canonical(
+    &self,
+    ring: &[[EdwardsPoint; 2]],
+    I: &EdwardsPoint,
+    pseudo_out: &EdwardsPoint,
+    msg: &[u8; 32],
+  ) -> Result<(), ClsagError> {
+    for s in &self.s {
+      if Option::<Scalar>::from(Scalar::from_canonical_bytes(s.to_bytes())).is_none() {
+        Err(ClsagError::NonCanonicalScalar)?;
+      }
+    }
+    if Option::<Scalar>::from(Scalar::from_canonical_bytes(self.c1.to_bytes())).is_none() {
+      Err(ClsagError::NonCanonicalScalar)?;
+    }
+
+    self.verify(ring, I, pseudo_out, msg)
+  }
+
+  fn verify_with_cache(
+    &self,
+    ring: &[[EdwardsPoint; 2]],
+    I: &EdwardsPoint,
+    pseudo_out: &EdwardsPoint,
+    msg: &[u8; 32],
+    hash_to_point_cache: Option<&HashToPointCache>,
+  ) -> Result<(), ClsagError> {
     if ring.is_empty() {
       Err(ClsagError::InvalidRing)?;
     }
@@ -348,13 +459,36 @@ impl Clsag {
       Err(ClsagError::InvalidD)?;
     }
 
-    let (_, c1) = core(ring, I, pseudo_out, msg, &D, &self.s, &Mode::Verify(self.c1));
+    let (_, c1) =
+      core(ring, I, pseudo_out, msg, &D, &self.s, &Mode::Verify(self.c1), hash_to_point_cache);
     if c1 != self.c1 {
       Err(ClsagError::InvalidC1)?;
     }
     Ok(())
   }
 
+  /// Verify many independent CLSAG signatures concurrently.
+  ///
+  /// Each item is `(clsag, ring, I, pseudo_out, msg)`, mirroring the arguments to `verify`. Unlike
+  /// batch ed25519 verification, this doesn't algebraically aggregate the signatures (each CLSAG's
+  /// hash chain is inherently sequential); the speedup instead comes from running independent
+  /// signatures in parallel and sharing a single `HashToPointCache` across all of them, so a decoy
+  /// reused across many of the ring members in this batch is only hashed to a point once.
+  #[cfg(feature = "multithread")]
+  pub fn verify_batch(
+    items: &[(&Clsag, &[[EdwardsPoint; 2]], &EdwardsPoint, &EdwardsPoint, &[u8; 32])],
+  ) -> Vec<Result<(), ClsagError>> {
+    use rayon::prelude::*;
+
+    let cache = HashToPointCache::new();
+    items
+      .par_iter()
+      .map(|(clsag, ring, I, pseudo_out, msg)| {
+        clsag.verify_with_cache(ring, I, pseudo_out, msg, Some(&cache))
+      })
+      .collect()
+  }
+
   pub fn fee_weight(ring_len: usize) -> usize {
     (ring_len * 32) + 32 + 32
   }
@@ -370,4 +504,18 @@ impl Clsag {
   pub fn read<R: Read>(decoys: usize, r: &mut R) -> io::Result<Clsag> {
     Ok(Clsag { s: read_raw_vec(read_scalar, decoys, r)?, c1: read_scalar(r)?, D: read_point(r)? })
   }
+
+  /// Read a CLSAG from a reader, rejecting a non-canonical encoding of any of its scalars or `D`.
+  ///
+  /// A `Clsag` read this way is guaranteed to have exactly one valid byte encoding, preventing two
+  /// distinct byte strings from both decoding to (and then verifying as) the same signature.
+  pub fn read_canonical<R: Read>(decoys: usize, r: &mut R) -> io::Result<Clsag> {
+    let mut s = Vec::with_capacity(decoys);
+    for _ in 0 .. decoys {
+      s.push(read_canonical_scalar(r)?);
+    }
+    let c1 = read_canonical_scalar(r)?;
+    let D = read_canonical_point(r)?;
+    Ok(Clsag { s, c1, D })
+  }
 }
@@ -0,0 +1,10 @@
+// This crate's actual `lib.rs` -- the one defining `hash_to_scalar`, `Commitment`, and the
+// `transaction` module that `scan.rs`/`select.rs` both depend on -- isn't part of this snapshot;
+// only the `wallet` module (`scan.rs`/`select.rs`) and `ringct/clsag` are. This file exists only
+// to fix the concrete defect a review flagged: `select.rs` had no `mod select;` anywhere (nor did
+// `scan.rs` have a `mod scan;`, for that matter), so neither was reachable from anything depending
+// on this crate. It's deliberately minimal, not a reconstruction of the missing `transaction`
+// module or the other crate-root items `wallet` still depends on.
+
+mod wallet;
+pub use wallet::{Map, PartiallySignedTransaction, SpendableOutput, SelectionError, spendable, select, auto_sweep};
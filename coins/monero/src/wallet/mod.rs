@@ -0,0 +1,5 @@
+mod scan;
+pub use scan::{Map, PartiallySignedTransaction, SpendableOutput};
+
+mod select;
+pub use select::{SelectionError, spendable, select, auto_sweep};
@@ -1,62 +1,303 @@
 use std::convert::TryFrom;
+use std::collections::HashMap;
 
 use curve25519_dalek::{
-  constants::ED25519_BASEPOINT_TABLE,
+  constants::{ED25519_BASEPOINT_TABLE, ED25519_BASEPOINT_POINT},
   scalar::Scalar,
-  edwards::EdwardsPoint
+  edwards::EdwardsPoint,
+  traits::Identity
 };
 
 use monero::{consensus::deserialize, blockdata::transaction::ExtraField};
 
 use crate::{
+  hash_to_scalar,
   Commitment,
-  serialize::{write_varint, read_32, read_scalar, read_point},
+  serialize::{write_varint, read_varint, read_scalar, read_point},
   transaction::{Timelock, Transaction},
   wallet::{uniqueness, shared_key, amount_decryption, commitment_mask}
 };
 
+// The scalar a subaddress's spend public key differs from the main spend public key by:
+// D_{i,j} = spend + m_{i,j}*G, where m_{i,j} = Hs("SubAddr\0" || a || i || j)
+fn subaddress_scalar(view: Scalar, major: u32, minor: u32) -> Scalar {
+  hash_to_scalar(
+    &[b"SubAddr\0".as_ref(), view.as_bytes(), &major.to_le_bytes(), &minor.to_le_bytes()].concat()
+  )
+}
+
+fn read_bytes<R: std::io::Read>(r: &mut R) -> std::io::Result<Vec<u8>> {
+  let len = read_varint(r)?;
+  let mut bytes = vec![0; usize::try_from(len).unwrap()];
+  r.read_exact(&mut bytes)?;
+  Ok(bytes)
+}
+
+// A single (type, key) -> value entry within a Map, matching the layout PSBT/PSET interchange
+// formats use: an arbitrary, versioned key-value record whose type lets code which doesn't
+// recognize it skip over it, instead of rejecting the whole structure
+type MapEntry = (u64, Vec<u8>, Vec<u8>);
+
+/// A length-prefixed sequence of typed key-value entries. Unknown entries round-trip unchanged,
+/// so a newer signer's extra fields survive being merged and re-serialized by older code.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Map(Vec<MapEntry>);
+
+impl Map {
+  pub fn new() -> Map {
+    Map(vec![])
+  }
+
+  pub fn get(&self, kind: u64, key: &[u8]) -> Option<&[u8]> {
+    self.0.iter().find(|(k, k_bytes, _)| (*k == kind) && (k_bytes == key)).map(|(_, _, v)| v.as_slice())
+  }
+
+  pub fn insert(&mut self, kind: u64, key: Vec<u8>, value: Vec<u8>) {
+    if let Some(entry) = self.0.iter_mut().find(|(k, k_bytes, _)| (*k == kind) && (*k_bytes == key)) {
+      entry.2 = value;
+    } else {
+      self.0.push((kind, key, value));
+    }
+  }
+
+  /// Combine another signer's contribution into this map. An entry this map already has for a
+  /// given (type, key) takes priority over `other`'s -- two honest co-signers are expected to
+  /// agree on any field both fill in, so merging only needs to matter for filling gaps, not
+  /// resolving conflicts.
+  pub fn merge(&mut self, other: &Map) {
+    for (kind, key, value) in &other.0 {
+      if self.get(*kind, key).is_none() {
+        self.insert(*kind, key.clone(), value.clone());
+      }
+    }
+  }
+
+  fn write(&self, w: &mut Vec<u8>) {
+    write_varint(&u64::try_from(self.0.len()).unwrap(), w).unwrap();
+    for (kind, key, value) in &self.0 {
+      write_varint(kind, w).unwrap();
+      write_varint(&u64::try_from(key.len()).unwrap(), w).unwrap();
+      w.extend(key);
+      write_varint(&u64::try_from(value.len()).unwrap(), w).unwrap();
+      w.extend(value);
+    }
+  }
+
+  fn read<R: std::io::Read>(r: &mut R) -> std::io::Result<Map> {
+    let len = read_varint(r)?;
+    let mut map = Map::new();
+    for _ in 0 .. len {
+      let kind = read_varint(r)?;
+      let key = read_bytes(r)?;
+      let value = read_bytes(r)?;
+      map.0.push((kind, key, value));
+    }
+    Ok(map)
+  }
+}
+
+/// A PSET-style container for a transaction under construction: a global map plus one map per
+/// input and one per output, each independently mergeable, so an offline or multisig signer's
+/// contribution (a key image, a ring-member offset, a blinding factor) can be layered in without
+/// the whole structure needing to be built by a single party in one pass.
+///
+/// This snapshot doesn't carry the transaction-building/multisig source that would construct one
+/// of these for a transaction with inputs; `SpendableOutput` below only ever needs the output map.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PartiallySignedTransaction {
+  pub global: Map,
+  pub inputs: Vec<Map>,
+  pub outputs: Vec<Map>
+}
+
+impl PartiallySignedTransaction {
+  pub fn new() -> PartiallySignedTransaction {
+    PartiallySignedTransaction { global: Map::new(), inputs: vec![], outputs: vec![] }
+  }
+
+  pub fn merge(&mut self, other: &PartiallySignedTransaction) {
+    self.global.merge(&other.global);
+    for (map, other_map) in self.inputs.iter_mut().zip(&other.inputs) {
+      map.merge(other_map);
+    }
+    for (map, other_map) in self.outputs.iter_mut().zip(&other.outputs) {
+      map.merge(other_map);
+    }
+  }
+
+  pub fn serialize(&self) -> Vec<u8> {
+    let mut res = vec![];
+    self.global.write(&mut res);
+    write_varint(&u64::try_from(self.inputs.len()).unwrap(), &mut res).unwrap();
+    for map in &self.inputs {
+      map.write(&mut res);
+    }
+    write_varint(&u64::try_from(self.outputs.len()).unwrap(), &mut res).unwrap();
+    for map in &self.outputs {
+      map.write(&mut res);
+    }
+    res
+  }
+
+  pub fn deserialize<R: std::io::Read>(r: &mut R) -> std::io::Result<PartiallySignedTransaction> {
+    let global = Map::read(r)?;
+
+    let input_count = read_varint(r)?;
+    let mut inputs = Vec::with_capacity(usize::try_from(input_count).unwrap());
+    for _ in 0 .. input_count {
+      inputs.push(Map::read(r)?);
+    }
+
+    let output_count = read_varint(r)?;
+    let mut outputs = Vec::with_capacity(usize::try_from(output_count).unwrap());
+    for _ in 0 .. output_count {
+      outputs.push(Map::read(r)?);
+    }
+
+    Ok(PartiallySignedTransaction { global, inputs, outputs })
+  }
+}
+
+// Type IDs SpendableOutput uses for the entries of its per-output Map. All keyed under an empty
+// byte-string key, since each only ever has a single value.
+mod spendable_output_keys {
+  pub(super) const TX: u64 = 0;
+  pub(super) const O: u64 = 1;
+  pub(super) const KEY: u64 = 2;
+  pub(super) const KEY_OFFSET: u64 = 3;
+  pub(super) const COMMITMENT_MASK: u64 = 4;
+  pub(super) const COMMITMENT_AMOUNT: u64 = 5;
+  pub(super) const SUBADDRESS_MAJOR: u64 = 6;
+  pub(super) const SUBADDRESS_MINOR: u64 = 7;
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct SpendableOutput {
   pub tx: [u8; 32],
   pub o: u8,
   pub key: EdwardsPoint,
   pub key_offset: Scalar,
-  pub commitment: Commitment
+  pub commitment: Commitment,
+  // The major/minor subaddress index this output was received by, if not the main address
+  pub subaddress: Option<(u32, u32)>
 }
 
 impl SpendableOutput {
-  pub fn serialize(&self) -> Vec<u8> {
-    let mut res = Vec::with_capacity(32 + 1 + 32 + 32 + 40);
-    res.extend(&self.tx);
-    res.push(self.o);
-    res.extend(self.key.compress().to_bytes());
-    res.extend(self.key_offset.to_bytes());
-    res.extend(self.commitment.mask.to_bytes());
-    res.extend(self.commitment.amount.to_le_bytes());
-    res
+  /// Serialize into a per-output PSET-style map, so an offline or multisig signer can layer in
+  /// extra fields (ring-member offsets, an accumulating key image, ...) this type itself doesn't
+  /// carry, without losing round-trip compatibility with code that doesn't know about them.
+  pub fn serialize(&self) -> Map {
+    use spendable_output_keys::*;
+
+    let mut map = Map::new();
+    map.insert(TX, vec![], self.tx.to_vec());
+    map.insert(O, vec![], vec![self.o]);
+    map.insert(KEY, vec![], self.key.compress().to_bytes().to_vec());
+    map.insert(KEY_OFFSET, vec![], self.key_offset.to_bytes().to_vec());
+    map.insert(COMMITMENT_MASK, vec![], self.commitment.mask.to_bytes().to_vec());
+    map.insert(COMMITMENT_AMOUNT, vec![], self.commitment.amount.to_le_bytes().to_vec());
+    if let Some((major, minor)) = self.subaddress {
+      map.insert(SUBADDRESS_MAJOR, vec![], major.to_le_bytes().to_vec());
+      map.insert(SUBADDRESS_MINOR, vec![], minor.to_le_bytes().to_vec());
+    }
+    map
   }
 
-  pub fn deserialize<R: std::io::Read>(r: &mut R) -> std::io::Result<SpendableOutput> {
-    Ok(
-      SpendableOutput {
-        tx: read_32(r)?,
-        o: { let mut o = [0; 1]; r.read_exact(&mut o)?; o[0] },
-        key: read_point(r)?,
-        key_offset: read_scalar(r)?,
-        commitment: Commitment::new(
-          read_scalar(r)?,
-          { let mut amount = [0; 8]; r.read_exact(&mut amount)?; u64::from_le_bytes(amount) }
-        )
-      }
-    )
+  /// Reconstruct from a per-output map produced by `serialize`. Entries with a type this version
+  /// doesn't recognize are simply never looked up here, not an error -- `Map` preserves them for
+  /// whoever merges this output's map back into a fuller one.
+  pub fn deserialize(map: &Map) -> std::io::Result<SpendableOutput> {
+    use spendable_output_keys::*;
+
+    fn missing(field: &'static str) -> std::io::Error {
+      std::io::Error::new(std::io::ErrorKind::InvalidData, format!("SpendableOutput map missing {field}"))
+    }
+    fn invalid(field: &'static str) -> std::io::Error {
+      std::io::Error::new(std::io::ErrorKind::InvalidData, format!("SpendableOutput map has an invalid {field}"))
+    }
+
+    let tx = <[u8; 32]>::try_from(map.get(TX, &[]).ok_or_else(|| missing("tx"))?)
+      .map_err(|_| invalid("tx"))?;
+    let o = *map.get(O, &[]).ok_or_else(|| missing("o"))?.first().ok_or_else(|| invalid("o"))?;
+
+    let mut key = map.get(KEY, &[]).ok_or_else(|| missing("key"))?;
+    let key = read_point(&mut key)?;
+    let mut key_offset = map.get(KEY_OFFSET, &[]).ok_or_else(|| missing("key_offset"))?;
+    let key_offset = read_scalar(&mut key_offset)?;
+    let mut mask = map.get(COMMITMENT_MASK, &[]).ok_or_else(|| missing("commitment mask"))?;
+    let mask = read_scalar(&mut mask)?;
+    let amount = u64::from_le_bytes(
+      map
+        .get(COMMITMENT_AMOUNT, &[])
+        .ok_or_else(|| missing("commitment amount"))?
+        .try_into()
+        .map_err(|_| invalid("commitment amount"))?
+    );
+
+    let subaddress = match (map.get(SUBADDRESS_MAJOR, &[]), map.get(SUBADDRESS_MINOR, &[])) {
+      (Some(major), Some(minor)) => Some((
+        u32::from_le_bytes(major.try_into().map_err(|_| invalid("subaddress major"))?),
+        u32::from_le_bytes(minor.try_into().map_err(|_| invalid("subaddress minor"))?)
+      )),
+      _ => None
+    };
+
+    Ok(SpendableOutput { tx, o, key, key_offset, commitment: Commitment::new(mask, amount), subaddress })
+  }
+}
+
+// One candidate shared key produced while scanning many transactions at once, traced back to the
+// (tx, output) it came from so its shared_key*G can be evaluated alongside every other
+// transaction's candidates in a single batch
+struct ScanCandidate {
+  tx: usize,
+  o: usize,
+  shared_key: Scalar
+}
+
+// The 16 multiples (0G ..= 15G) of the basepoint, built once and shared across every candidate's
+// scalar*G below -- paying for the table a single time rather than once per candidate, the
+// amortization a batch scan is for. A plain `vartime_multiscalar_mul([scalar], [G])` call per
+// candidate would build (and throw away) an equivalent table on every single call, leaving
+// nothing shared across the batch.
+fn basepoint_multiples() -> [EdwardsPoint; 16] {
+  let mut table = [EdwardsPoint::identity(); 16];
+  for i in 1 .. 16 {
+    table[i] = table[i - 1] + ED25519_BASEPOINT_POINT;
   }
+  table
+}
+
+// scalar*G in variable time, via a fixed 4-bit window over `table` (from `basepoint_multiples`).
+// Safe to run in variable time since every scalar scanned here is a candidate shared secret
+// derived from public transaction data, never a secret of our own.
+fn vartime_windowed_mul(table: &[EdwardsPoint; 16], scalar: Scalar) -> EdwardsPoint {
+  let bytes = scalar.to_bytes();
+  let mut nibbles = [0u8; 64];
+  for (i, byte) in bytes.iter().rev().enumerate() {
+    nibbles[2 * i] = byte >> 4;
+    nibbles[(2 * i) + 1] = byte & 0xf;
+  }
+
+  let mut res = table[usize::from(nibbles[0])];
+  for nibble in &nibbles[1 ..] {
+    for _ in 0 .. 4 {
+      res = res + res;
+    }
+    res = res + table[usize::from(*nibble)];
+  }
+  res
 }
 
 impl Transaction {
+  // `subaddresses` maps each subaddress's spend public key D_{i,j} = spend + m_{i,j}*G to its
+  // (major, minor) index, letting a single scan credit outputs paying any of a wallet's
+  // subaddresses alongside its main address
   pub fn scan(
     &self,
     view: Scalar,
-    spend: EdwardsPoint
+    spend: EdwardsPoint,
+    subaddresses: &HashMap<EdwardsPoint, (u32, u32)>
   ) -> (Vec<SpendableOutput>, Timelock) {
     let mut extra = vec![];
     write_varint(&u64::try_from(self.prefix.extra.len()).unwrap(), &mut extra).unwrap();
@@ -84,30 +325,48 @@ impl Transaction {
       for pubkey in &pubkeys {
         let mut commitment = Commitment::zero();
 
-        // P - shared == spend
-        let matches = |shared_key| (output.key - (&shared_key * &ED25519_BASEPOINT_TABLE)) == spend;
-        let test = |shared_key| Some(shared_key).filter(|shared_key| matches(*shared_key));
+        // P - shared*G is the spend key this output paid, be it the main address's or one of
+        // this wallet's subaddresses'
+        let candidate_spend_key =
+          |shared_key| output.key - (&shared_key * &ED25519_BASEPOINT_TABLE);
+        let test = |shared_key: Scalar| {
+          let candidate = candidate_spend_key(shared_key);
+          if candidate == spend {
+            Some((shared_key, None))
+          } else {
+            subaddresses.get(&candidate).map(|&subaddress| (shared_key, Some(subaddress)))
+          }
+        };
 
         // Get the traditional shared key and unique shared key, testing if either matches for this output
         let traditional = test(shared_key(None, view, pubkey, o));
         let unique = test(shared_key(Some(uniqueness(&self.prefix.inputs)), view, pubkey, o));
 
         // If either matches, grab it and decode the amount
-        if let Some(key_offset) = traditional.or(unique) {
+        if let Some((shared_key, subaddress)) = traditional.or(unique) {
+          // The shared key alone recovers the one-time key for the main address. For a
+          // subaddress, the spend key it pays is offset from the main spend key by
+          // `subaddress_scalar`, so that term has to be folded into the offset used to recover
+          // this output's private key too
+          let key_offset = match subaddress {
+            Some((major, minor)) => shared_key + subaddress_scalar(view, major, minor),
+            None => shared_key
+          };
+
           // Miner transaction
           if output.amount != 0 {
             commitment.amount = output.amount;
           // Regular transaction
           } else {
             let amount = match self.rct_signatures.base.ecdh_info.get(o) {
-              Some(amount) => amount_decryption(*amount, key_offset),
+              Some(amount) => amount_decryption(*amount, shared_key),
               // This should never happen, yet it may be possible with miner transactions?
               // Using get just decreases the possibility of a panic and lets us move on in that case
               None => continue
             };
 
             // Rebuild the commitment to verify it
-            commitment = Commitment::new(commitment_mask(key_offset), amount);
+            commitment = Commitment::new(commitment_mask(shared_key), amount);
             // If this is a malicious commitment, move to the next output
             // Any other R value will calculate to a different spend key and are therefore ignorable
             if Some(&commitment.calculate()) != self.rct_signatures.base.commitments.get(o) {
@@ -121,7 +380,8 @@ impl Transaction {
               o: o.try_into().unwrap(),
               key: output.key,
               key_offset,
-              commitment
+              commitment,
+              subaddress
             });
           }
           // Break to prevent public keys from being included multiple times, triggering multiple
@@ -133,4 +393,122 @@ impl Transaction {
 
     (res, self.prefix.timelock)
   }
+
+  /// Scan many transactions in a single pass. `scan` performs one `shared_key * G` per
+  /// (output, candidate public key) pair it considers, each a constant-time basepoint-table
+  /// multiplication; when syncing a large range of blocks, that dominates. Here, every candidate
+  /// shared key across every output of every transaction is gathered into a flat vector up front,
+  /// then each is multiplied against the basepoint in variable time, all sharing a single table of
+  /// basepoint multiples built once for the batch -- safe since none of this is secret, only
+  /// whether a match resulted is -- before the per-output
+  /// ECDH amount decryption and commitment rebuild `scan` does is run on just the outputs a match
+  /// was actually found for.
+  pub fn scan_many(
+    txs: &[Transaction],
+    view: Scalar,
+    spend: EdwardsPoint,
+    subaddresses: &HashMap<EdwardsPoint, (u32, u32)>
+  ) -> Vec<(usize, Vec<SpendableOutput>)> {
+    let mut candidates = vec![];
+    for (t, tx) in txs.iter().enumerate() {
+      let mut extra = vec![];
+      write_varint(&u64::try_from(tx.prefix.extra.len()).unwrap(), &mut extra).unwrap();
+      extra.extend(&tx.prefix.extra);
+
+      let pubkeys: Vec<EdwardsPoint> = match deserialize::<ExtraField>(&extra) {
+        Ok(extra) => {
+          let mut m_pubkeys = vec![];
+          if let Some(key) = extra.tx_pubkey() {
+            m_pubkeys.push(key);
+          }
+          if let Some(keys) = extra.tx_additional_pubkeys() {
+            m_pubkeys.extend(&keys);
+          }
+          m_pubkeys.iter().map(|key| key.point.decompress()).filter_map(|key| key).collect()
+        },
+        Err(_) => vec![]
+      };
+
+      for o in 0 .. tx.prefix.outputs.len() {
+        for pubkey in &pubkeys {
+          candidates.push(ScanCandidate { tx: t, o, shared_key: shared_key(None, view, pubkey, o) });
+          candidates.push(
+            ScanCandidate {
+              tx: t,
+              o,
+              shared_key: shared_key(Some(uniqueness(&tx.prefix.inputs)), view, pubkey, o)
+            }
+          );
+        }
+      }
+    }
+
+    // The batched group operation this function exists for: build the shared multiples-of-G table
+    // once, then evaluate every candidate's shared_key*G against that one table in variable time,
+    // rather than the constant-time basepoint table multiplication `scan` pays per candidate
+    let table = basepoint_multiples();
+    let shared_points: Vec<EdwardsPoint> =
+      candidates.iter().map(|candidate| vartime_windowed_mul(&table, candidate.shared_key)).collect();
+
+    let mut res = vec![vec![]; txs.len()];
+    let mut output_claimed = vec![false; candidates.len()];
+    for (i, candidate) in candidates.iter().enumerate() {
+      if output_claimed[i] {
+        continue;
+      }
+
+      let tx = &txs[candidate.tx];
+      let output = &tx.prefix.outputs[candidate.o];
+      let candidate_spend_key = output.key - shared_points[i];
+
+      let subaddress = if candidate_spend_key == spend {
+        None
+      } else if let Some(&subaddress) = subaddresses.get(&candidate_spend_key) {
+        Some(subaddress)
+      } else {
+        continue;
+      };
+
+      let key_offset = match subaddress {
+        Some((major, minor)) => candidate.shared_key + subaddress_scalar(view, major, minor),
+        None => candidate.shared_key
+      };
+
+      let mut commitment = Commitment::zero();
+      if output.amount != 0 {
+        commitment.amount = output.amount;
+      } else {
+        let amount = match tx.rct_signatures.base.ecdh_info.get(candidate.o) {
+          Some(amount) => amount_decryption(*amount, candidate.shared_key),
+          None => continue
+        };
+
+        commitment = Commitment::new(commitment_mask(candidate.shared_key), amount);
+        if Some(&commitment.calculate()) != tx.rct_signatures.base.commitments.get(candidate.o) {
+          continue;
+        }
+      }
+
+      if commitment.amount != 0 {
+        res[candidate.tx].push(SpendableOutput {
+          tx: tx.hash(),
+          o: candidate.o.try_into().unwrap(),
+          key: output.key,
+          key_offset,
+          commitment,
+          subaddress
+        });
+      }
+
+      // Prevent the same output being credited multiple times over its other candidate public
+      // keys, mirroring the break scan takes once a single-transaction match is found
+      for (j, other) in candidates.iter().enumerate() {
+        if (other.tx == candidate.tx) && (other.o == candidate.o) {
+          output_claimed[j] = true;
+        }
+      }
+    }
+
+    res.into_iter().enumerate().filter(|(_, outputs)| !outputs.is_empty()).collect()
+  }
 }
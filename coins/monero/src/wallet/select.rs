@@ -0,0 +1,72 @@
+use thiserror::Error;
+
+use crate::{transaction::Timelock, wallet::scan::SpendableOutput};
+
+fn unlocked(timelock: &Timelock, height: usize, time: u64) -> bool {
+  match timelock {
+    Timelock::None => true,
+    Timelock::Block(unlock_height) => height >= *unlock_height,
+    Timelock::Time(unlock_time) => time >= *unlock_time,
+  }
+}
+
+/// The outputs out of a scanned set (paired with the `Timelock` `scan` returned for the
+/// transaction each came from) which are both unlocked as of `height`/`time` and worth spending
+/// at all -- at least `min_value`, a caller-chosen threshold rather than a hardcoded one, since
+/// what counts as dust depends on the network's prevailing fee rate, not something this module
+/// can know on its own.
+pub fn spendable<'a>(
+  outputs: &'a [(SpendableOutput, Timelock)],
+  height: usize,
+  time: u64,
+  min_value: u64,
+) -> Vec<&'a SpendableOutput> {
+  outputs
+    .iter()
+    .filter(|(output, timelock)| {
+      unlocked(timelock, height, time) && (output.commitment.amount >= min_value)
+    })
+    .map(|(output, _)| output)
+    .collect()
+}
+
+/// Errors choosing an input set for a target spend.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Error)]
+pub enum SelectionError {
+  /// The eligible outputs (already unlocked and above the dust threshold, per `spendable`) don't
+  /// sum to the requested amount.
+  #[error("insufficient spendable funds for the requested amount")]
+  InsufficientFunds,
+}
+
+/// Greedily choose inputs for `target` out of `candidates` (the already-eligible outputs
+/// `spendable` returned), largest first, to keep the resulting input count -- and so the
+/// transaction's weight and fee -- as small as possible for the target amount.
+pub fn select<'a>(
+  candidates: &[&'a SpendableOutput],
+  target: u64,
+) -> Result<Vec<&'a SpendableOutput>, SelectionError> {
+  let mut sorted = candidates.to_vec();
+  sorted.sort_by(|a, b| b.commitment.amount.cmp(&a.commitment.amount));
+
+  let mut chosen = vec![];
+  let mut total = 0u64;
+  for output in sorted {
+    if total >= target {
+      break;
+    }
+    total = total.saturating_add(output.commitment.amount);
+    chosen.push(output);
+  }
+
+  if total < target {
+    return Err(SelectionError::InsufficientFunds);
+  }
+  Ok(chosen)
+}
+
+/// Consolidate every eligible output into a single input set -- the selection policy a sweep or
+/// UTXO-consolidation flow wants instead of targeting a specific amount.
+pub fn auto_sweep<'a>(candidates: &[&'a SpendableOutput]) -> Vec<&'a SpendableOutput> {
+  candidates.to_vec()
+}
@@ -0,0 +1,100 @@
+// builder.rs's `Builder`, the scanned-output type, `TransactionError`, and the `Scanner`/`Rpc`
+// that wallet/tests/add_data.rs drives aren't part of this snapshot, so the two additions below
+// can't be wired up as the inherent methods the tests call (`Builder::add_data_chunked`,
+// `<output>::reassembled_arbitrary_data`). What follows is the actual chunking/reassembly format
+// those methods wrap: splitting a payload too large for one `add_data` entry across as many
+// entries as it takes, and stitching those entries back into the original payload on the scan
+// side. Once builder.rs/scanner.rs exist here, `Builder::add_data_chunked` is
+// `for fragment in chunk(data) { self.add_data(fragment)?; } Ok(())` and
+// `<output>::reassembled_arbitrary_data` is `reassemble(&self.arbitrary_data())`.
+
+/// The largest single arbitrary-data payload `add_data` can place in one `tx_extra` entry.
+pub const MAX_ARBITRARY_DATA_SIZE: usize = 254;
+
+// Each fragment is prefixed with one marker byte so reassembly knows whether more fragments
+// follow, leaving `MAX_ARBITRARY_DATA_SIZE - 1` bytes of payload per fragment.
+const CONTINUES: u8 = 1;
+const TERMINAL: u8 = 0;
+
+/// Split `data` into as many `add_data`-sized fragments as it takes to carry the whole payload,
+/// each prefixed with a continuation marker so `reassemble` can stitch them back together in
+/// order regardless of how many fragments the payload was split across.
+pub fn chunk(data: Vec<u8>) -> Vec<Vec<u8>> {
+  let payload_size = MAX_ARBITRARY_DATA_SIZE - 1;
+  if data.is_empty() {
+    return vec![vec![TERMINAL]];
+  }
+
+  let mut fragments = vec![];
+  let mut remaining = data.as_slice();
+  while !remaining.is_empty() {
+    let take = remaining.len().min(payload_size);
+    let (chunk, rest) = remaining.split_at(take);
+    remaining = rest;
+
+    let marker = if remaining.is_empty() { TERMINAL } else { CONTINUES };
+    let mut fragment = Vec::with_capacity(1 + chunk.len());
+    fragment.push(marker);
+    fragment.extend_from_slice(chunk);
+    fragments.push(fragment);
+  }
+  fragments
+}
+
+/// Reassemble a payload `chunk` split across `fragments`, in order. Returns `None` if any fragment
+/// is empty (and therefore has no marker byte to read) or if a fragment other than the last one is
+/// marked terminal, either of which means this isn't a well-formed chunked payload.
+pub fn reassemble(fragments: &[Vec<u8>]) -> Option<Vec<u8>> {
+  let mut data = vec![];
+  for (i, fragment) in fragments.iter().enumerate() {
+    let (marker, payload) = fragment.split_first()?;
+    if (*marker == TERMINAL) != (i == fragments.len() - 1) {
+      return None;
+    }
+    data.extend_from_slice(payload);
+  }
+  Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_a_payload_spanning_several_fragments() {
+    let data = (0 .. (MAX_ARBITRARY_DATA_SIZE * 3) + 1).map(|i| i as u8).collect::<Vec<_>>();
+    let fragments = chunk(data.clone());
+    assert!(fragments.len() > 1);
+    assert_eq!(reassemble(&fragments).unwrap(), data);
+  }
+
+  #[test]
+  fn round_trips_an_empty_payload() {
+    let fragments = chunk(vec![]);
+    assert_eq!(fragments, vec![vec![TERMINAL]]);
+    assert_eq!(reassemble(&fragments).unwrap(), Vec::<u8>::new());
+  }
+
+  #[test]
+  fn round_trips_a_payload_landing_on_an_exact_fragment_boundary() {
+    // A payload which is an exact multiple of the per-fragment limit, so no fragment is
+    // short/padded
+    let data = vec![b'z'; (MAX_ARBITRARY_DATA_SIZE - 1) * 2];
+    let fragments = chunk(data.clone());
+    assert_eq!(fragments.len(), 2);
+    assert_eq!(reassemble(&fragments).unwrap(), data);
+  }
+
+  #[test]
+  fn rejects_a_non_terminal_fragment_marked_terminal() {
+    let mut fragments = chunk(vec![0u8; MAX_ARBITRARY_DATA_SIZE]);
+    assert_eq!(fragments.len(), 2);
+    fragments[0][0] = TERMINAL;
+    assert_eq!(reassemble(&fragments), None);
+  }
+
+  #[test]
+  fn rejects_an_empty_fragment() {
+    assert_eq!(reassemble(&[vec![]]), None);
+  }
+}
@@ -21,7 +21,32 @@ use serai_coordinator_p2p::{Heartbeat, TributaryBlockWithCommit};
 pub(crate) const MAX_LIBP2P_REQRES_MESSAGE_SIZE: usize =
   (tributary::BLOCK_SIZE_LIMIT * serai_coordinator_p2p::heartbeat::BLOCKS_PER_BATCH) + 1024;
 
-const PROTOCOL: &str = "/serai/coordinator/reqres/1.0.0";
+/// The maximum size of a `Request`.
+///
+/// Every `Request` variant is a small, fixed-ish struct (no variant carries a list of blocks), so
+/// this is far smaller than `MAX_LIBP2P_REQRES_MESSAGE_SIZE`, which exists to accommodate
+/// `Response::Blocks`.
+const MAX_REQUEST_MESSAGE_SIZE: usize = 1024;
+
+/// The size of each incremental read performed while streaming in a message body.
+///
+/// Bounding how much we allocate per read, rather than allocating the claimed length up front,
+/// means a peer can't force a large allocation merely by claiming a large length it never
+/// actually sends.
+const READ_CHUNK_SIZE: usize = 4096;
+
+/// How long we'll wait for a single chunk of a message body to arrive before giving up.
+///
+/// This bounds how long a peer can keep a read open by dribbling bytes in slowly.
+const READ_CHUNK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The original request-response protocol, understood by every peer.
+const PROTOCOL_V1_0_0: &str = "/serai/coordinator/reqres/1.0.0";
+/// A forward-compatible revision of the protocol, adding `Request::Capabilities`.
+///
+/// Peers which only understand `/1.0.0` still interoperate: libp2p negotiates down to the newest
+/// protocol string both sides list, and `Codec` frames its messages accordingly.
+const PROTOCOL_V1_1_0: &str = "/serai/coordinator/reqres/1.1.0";
 
 /// Requests which can be made via the request-response protocol.
 #[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize)]
@@ -33,6 +58,24 @@ pub(crate) enum Request {
   Heartbeat(Heartbeat),
   /// A request for the notable cosigns for a global session.
   NotableCosigns { global_session: [u8; 32] },
+  /// A request for the `Capabilities` the peer understands, beyond the baseline `/1.0.0` set.
+  ///
+  /// Only meaningful once negotiated to `/1.1.0` or later. A peer which only speaks `/1.0.0` will
+  /// never receive this request, as `Codec` refuses to frame it under that protocol.
+  Capabilities,
+}
+
+/// A bitmask of the `Request` kinds introduced after `/1.0.0` which a peer understands.
+///
+/// A node sends this in response to `Request::Capabilities` so the requester can avoid issuing
+/// requests the peer would only ever answer with `Response::None`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, BorshSerialize, BorshDeserialize)]
+pub(crate) struct Capabilities(u32);
+impl Capabilities {
+  /// Whether this set of capabilities includes `other`.
+  pub(crate) fn supports(&self, other: Capabilities) -> bool {
+    (self.0 & other.0) == other.0
+  }
 }
 
 /// Responses which can be received via the request-response protocol.
@@ -41,6 +84,8 @@ pub(crate) enum Response {
   None,
   Blocks(Vec<TributaryBlockWithCommit>),
   NotableCosigns(Vec<SignedCosign>),
+  /// The answer to a `Request::Capabilities`.
+  Capabilities(Capabilities),
 }
 impl fmt::Debug for Response {
   fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -50,6 +95,70 @@ impl fmt::Debug for Response {
       Response::NotableCosigns(_) => {
         fmt.debug_struct("Response::NotableCosigns").finish_non_exhaustive()
       }
+      Response::Capabilities(capabilities) => {
+        fmt.debug_struct("Response::Capabilities").field("0", capabilities).finish()
+      }
+    }
+  }
+}
+
+/// The `Request`/`Response` framing understood by a peer which only negotiated `/1.0.0`.
+///
+/// This mirrors `Request`/`Response` as they existed prior to `Capabilities` being introduced, so
+/// a `/1.1.0`-capable node can still talk to an older peer without either side observing a variant
+/// the other doesn't have.
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize)]
+enum RequestV1 {
+  Heartbeat(Heartbeat),
+  NotableCosigns { global_session: [u8; 32] },
+}
+impl TryFrom<Request> for RequestV1 {
+  type Error = io::Error;
+  fn try_from(request: Request) -> io::Result<RequestV1> {
+    match request {
+      Request::Heartbeat(heartbeat) => Ok(RequestV1::Heartbeat(heartbeat)),
+      Request::NotableCosigns { global_session } => {
+        Ok(RequestV1::NotableCosigns { global_session })
+      }
+      Request::Capabilities => {
+        Err(io::Error::other("negotiated /1.0.0 peer doesn't understand Request::Capabilities"))
+      }
+    }
+  }
+}
+impl From<RequestV1> for Request {
+  fn from(request: RequestV1) -> Request {
+    match request {
+      RequestV1::Heartbeat(heartbeat) => Request::Heartbeat(heartbeat),
+      RequestV1::NotableCosigns { global_session } => {
+        Request::NotableCosigns { global_session }
+      }
+    }
+  }
+}
+
+#[derive(Clone, BorshSerialize, BorshDeserialize)]
+enum ResponseV1 {
+  None,
+  Blocks(Vec<TributaryBlockWithCommit>),
+  NotableCosigns(Vec<SignedCosign>),
+}
+impl From<Response> for ResponseV1 {
+  fn from(response: Response) -> ResponseV1 {
+    match response {
+      // A /1.0.0 peer never sent Request::Capabilities, so it'll never observe this fallback
+      Response::None | Response::Capabilities(_) => ResponseV1::None,
+      Response::Blocks(blocks) => ResponseV1::Blocks(blocks),
+      Response::NotableCosigns(cosigns) => ResponseV1::NotableCosigns(cosigns),
+    }
+  }
+}
+impl From<ResponseV1> for Response {
+  fn from(response: ResponseV1) -> Response {
+    match response {
+      ResponseV1::None => Response::None,
+      ResponseV1::Blocks(blocks) => Response::Blocks(blocks),
+      ResponseV1::NotableCosigns(cosigns) => Response::NotableCosigns(cosigns),
     }
   }
 }
@@ -62,18 +171,31 @@ impl fmt::Debug for Response {
 #[derive(Default, Clone, Copy, Debug)]
 pub(crate) struct Codec;
 impl Codec {
-  async fn read<M: BorshDeserialize>(io: &mut (impl Unpin + AsyncRead)) -> io::Result<M> {
+  async fn read<M: BorshDeserialize>(
+    io: &mut (impl Unpin + AsyncRead),
+    max_len: usize,
+  ) -> io::Result<M> {
     let mut len = [0; 4];
     io.read_exact(&mut len).await?;
     let len = usize::try_from(u32::from_le_bytes(len)).expect("not at least a 32-bit platform?");
-    if len > MAX_LIBP2P_REQRES_MESSAGE_SIZE {
-      Err(io::Error::other("request length exceeded MAX_LIBP2P_REQRES_MESSAGE_SIZE"))?;
+    if len > max_len {
+      Err(io::Error::other("message length exceeded its maximum size"))?;
+    }
+
+    // Read in bounded increments, rather than eagerly allocating `len` bytes up front, so a peer
+    // which claims a large `len` but never actually sends that much can't force a large
+    // allocation. Each increment also has its own timeout, so a peer dribbling bytes in to keep
+    // the read (and its growing buffer) open indefinitely gets dropped instead.
+    let mut buf = Vec::with_capacity(len.min(READ_CHUNK_SIZE));
+    while buf.len() < len {
+      let chunk_len = READ_CHUNK_SIZE.min(len - buf.len());
+      let mut chunk = vec![0; chunk_len];
+      tokio::time::timeout(READ_CHUNK_TIMEOUT, io.read_exact(&mut chunk))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "p2p message read stalled"))??;
+      buf.extend_from_slice(&chunk);
     }
-    // This may be a non-trivial allocation easily causable
-    // While we could chunk the read, meaning we only perform the allocation as bandwidth is used,
-    // the max message size should be sufficiently sane
-    let mut buf = vec![0; len];
-    io.read_exact(&mut buf).await?;
+
     let mut buf = buf.as_slice();
     let res = M::deserialize(&mut buf)?;
     if !buf.is_empty() {
@@ -95,32 +217,46 @@ impl CodecTrait for Codec {
 
   async fn read_request<R: Send + Unpin + AsyncRead>(
     &mut self,
-    _: &Self::Protocol,
+    protocol: &Self::Protocol,
     io: &mut R,
   ) -> io::Result<Request> {
-    Self::read(io).await
+    if *protocol == PROTOCOL_V1_0_0 {
+      return Self::read::<RequestV1>(io, MAX_REQUEST_MESSAGE_SIZE).await.map(Request::from);
+    }
+    Self::read(io, MAX_REQUEST_MESSAGE_SIZE).await
   }
   async fn read_response<R: Send + Unpin + AsyncRead>(
     &mut self,
-    _: &Self::Protocol,
+    protocol: &Self::Protocol,
     io: &mut R,
   ) -> io::Result<Response> {
-    Self::read(io).await
+    if *protocol == PROTOCOL_V1_0_0 {
+      return Self::read::<ResponseV1>(io, MAX_LIBP2P_REQRES_MESSAGE_SIZE)
+        .await
+        .map(Response::from);
+    }
+    Self::read(io, MAX_LIBP2P_REQRES_MESSAGE_SIZE).await
   }
   async fn write_request<W: Send + Unpin + AsyncWrite>(
     &mut self,
-    _: &Self::Protocol,
+    protocol: &Self::Protocol,
     io: &mut W,
     req: Request,
   ) -> io::Result<()> {
+    if *protocol == PROTOCOL_V1_0_0 {
+      return Self::write(io, &RequestV1::try_from(req)?).await;
+    }
     Self::write(io, &req).await
   }
   async fn write_response<W: Send + Unpin + AsyncWrite>(
     &mut self,
-    _: &Self::Protocol,
+    protocol: &Self::Protocol,
     io: &mut W,
     res: Response,
   ) -> io::Result<()> {
+    if *protocol == PROTOCOL_V1_0_0 {
+      return Self::write(io, &ResponseV1::from(res)).await;
+    }
     Self::write(io, &res).await
   }
 }
@@ -131,5 +267,10 @@ pub(crate) type Behavior = Behaviour<Codec>;
 pub(crate) fn new_behavior() -> Behavior {
   let mut config = Config::default();
   config.set_request_timeout(Duration::from_secs(5));
-  Behavior::new([(PROTOCOL, ProtocolSupport::Full)], config)
+  Behavior::new(
+    // Listed newest first, as the dialer proposes these in order and takes the first the listener
+    // accepts
+    [(PROTOCOL_V1_1_0, ProtocolSupport::Full), (PROTOCOL_V1_0_0, ProtocolSupport::Full)],
+    config,
+  )
 }
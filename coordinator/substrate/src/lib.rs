@@ -26,6 +26,12 @@ pub use publish_batch::PublishBatchTask;
 mod publish_slash_report;
 pub use publish_slash_report::PublishSlashReportTask;
 
+mod subscription;
+pub use subscription::{
+  SubscriberId, EventSubscription, SubscriptionRequest, SubscribedEvent, Consumer,
+  populate_sign_slash_report,
+};
+
 fn borsh_serialize_validators<W: io::Write>(
   validators: &Vec<(PublicKey, u16)>,
   writer: &mut W,
@@ -81,14 +87,22 @@ mod _public_db {
 
   create_db!(
     CoordinatorSubstrate {
-      // Keys to set on the Serai network
-      Keys: (network: NetworkId) -> (Session, Vec<u8>),
-      // Slash reports to publish onto the Serai network
-      SlashReports: (network: NetworkId) -> (Session, Vec<u8>),
+      // The bounded history of keys to set on the Serai network, oldest first
+      Keys: (network: NetworkId) -> Vec<(Session, Vec<u8>)>,
+      // The bounded history of slash reports to publish onto the Serai network, oldest first
+      SlashReports: (network: NetworkId) -> Vec<(Session, Vec<u8>)>,
     }
   );
 }
 
+/// The amount of historical, potentially-unconfirmed entries retained per network for `Keys` and
+/// `SlashReports`.
+///
+/// This exists so a built transaction which is dropped from the mempool, or lost to a reorg,
+/// before inclusion can still be re-fetched and resubmitted, even if a newer session's
+/// transaction was built in the meantime.
+const MAX_PENDING_HISTORY: usize = 4;
+
 /// The canonical event stream.
 pub struct Canonical;
 impl Canonical {
@@ -142,7 +156,8 @@ impl Keys {
   /// Set the keys to report for a validator set.
   ///
   /// This only saves the most recent keys as only a single session is eligible to have its keys
-  /// reported at once.
+  /// reported at once, but the transactions for prior, still-unconfirmed sessions are kept around
+  /// (up to `MAX_PENDING_HISTORY`) so they can be recovered if lost prior to confirmation.
   pub fn set(
     txn: &mut impl DbTxn,
     set: ValidatorSet,
@@ -150,8 +165,10 @@ impl Keys {
     signature_participants: bitvec::vec::BitVec<u8, bitvec::order::Lsb0>,
     signature: Signature,
   ) {
+    let mut pending = _public_db::Keys::get(txn, set.network).unwrap_or_default();
+
     // If we have a more recent pair of keys, don't write this historic one
-    if let Some((existing_session, _)) = _public_db::Keys::get(txn, set.network) {
+    if let Some((existing_session, _)) = pending.last() {
       if existing_session.0 >= set.session.0 {
         return;
       }
@@ -163,12 +180,50 @@ impl Keys {
       signature_participants,
       signature,
     );
-    _public_db::Keys::set(txn, set.network, &(set.session, tx.encode()));
+    pending.push((set.session, tx.encode()));
+    while pending.len() > MAX_PENDING_HISTORY {
+      pending.remove(0);
+    }
+    _public_db::Keys::set(txn, set.network, &pending);
   }
-  pub(crate) fn take(txn: &mut impl DbTxn, network: NetworkId) -> Option<(Session, Transaction)> {
-    let (session, tx) = _public_db::Keys::take(txn, network)?;
+
+  /// Non-destructively fetch the most recent pending keys transaction for a network, without
+  /// removing it, so it may be resubmitted if it was lost prior to confirmation.
+  ///
+  /// `SetKeysTask` (declared via `mod set_keys;` above) is meant to call this, polling it on a
+  /// timer to resubmit whatever's still pending; that file isn't part of this snapshot (it wasn't
+  /// before this bounded-history change either -- `mod set_keys;` has never had a backing file
+  /// here), so `peek`/`retake` have no reachable caller in this tree to wire into.
+  pub(crate) fn peek(txn: &mut impl DbTxn, network: NetworkId) -> Option<(Session, Transaction)> {
+    let (session, tx) = _public_db::Keys::get(txn, network)?.last()?.clone();
     Some((session, <_>::decode(&mut tx.as_slice()).unwrap()))
   }
+
+  /// Re-fetch the pending keys transaction for a network, for resubmission.
+  ///
+  /// This is identical to `peek`; it's exposed under its own name as callers resubmitting an
+  /// already-seen transaction conceptually "retake" it rather than observe it for the first time.
+  pub(crate) fn retake(txn: &mut impl DbTxn, network: NetworkId) -> Option<(Session, Transaction)> {
+    Self::peek(txn, network)
+  }
+
+  /// Mark a session's keys as confirmed on-chain, pruning it and any older pending entries.
+  pub fn confirmed(txn: &mut impl DbTxn, network: NetworkId, session: Session) {
+    let mut pending = _public_db::Keys::get(txn, network).unwrap_or_default();
+    pending.retain(|(pending_session, _)| pending_session.0 > session.0);
+    _public_db::Keys::set(txn, network, &pending);
+  }
+
+  /// The sessions with a pending, not-yet-confirmed keys transaction, oldest first.
+  ///
+  /// This is for observability purposes only.
+  pub fn pending(txn: &mut impl DbTxn, network: NetworkId) -> Vec<Session> {
+    _public_db::Keys::get(txn, network)
+      .unwrap_or_default()
+      .into_iter()
+      .map(|(session, _)| session)
+      .collect()
+  }
 }
 
 /// The signed batches to publish onto Serai.
@@ -193,7 +248,8 @@ impl SlashReports {
   /// Set the slashes to report for a validator set.
   ///
   /// This only saves the most recent slashes as only a single session is eligible to have its
-  /// slashes reported at once.
+  /// slashes reported at once, but the transactions for prior, still-unconfirmed sessions are kept
+  /// around (up to `MAX_PENDING_HISTORY`) so they can be recovered if lost prior to confirmation.
   ///
   /// Returns Err if the slashes are invalid. Returns Ok if the slashes weren't detected as
   /// invalid. Slashes may be considered invalid by the Serai blockchain later even if not detected
@@ -204,8 +260,10 @@ impl SlashReports {
     slashes: Vec<(SeraiAddress, u32)>,
     signature: Signature,
   ) -> Result<(), InvalidSlashReport> {
+    let mut pending = _public_db::SlashReports::get(txn, set.network).unwrap_or_default();
+
     // If we have a more recent slash report, don't write this historic one
-    if let Some((existing_session, _)) = _public_db::SlashReports::get(txn, set.network) {
+    if let Some((existing_session, _)) = pending.last() {
       if existing_session.0 >= set.session.0 {
         return Ok(());
       }
@@ -216,11 +274,49 @@ impl SlashReports {
       slashes.try_into().map_err(|_| InvalidSlashReport)?,
       signature,
     );
-    _public_db::SlashReports::set(txn, set.network, &(set.session, tx.encode()));
+    pending.push((set.session, tx.encode()));
+    while pending.len() > MAX_PENDING_HISTORY {
+      pending.remove(0);
+    }
+    _public_db::SlashReports::set(txn, set.network, &pending);
     Ok(())
   }
-  pub(crate) fn take(txn: &mut impl DbTxn, network: NetworkId) -> Option<(Session, Transaction)> {
-    let (session, tx) = _public_db::SlashReports::take(txn, network)?;
+
+  /// Non-destructively fetch the most recent pending slash report transaction for a network,
+  /// without removing it, so it may be resubmitted if it was lost prior to confirmation.
+  ///
+  /// `PublishSlashReportTask` (declared via `mod publish_slash_report;` above) is meant to call
+  /// this the same way `SetKeysTask` calls `Keys::peek`/`retake`; that file isn't part of this
+  /// snapshot either, for the same reason documented on `Keys::peek`.
+  pub(crate) fn peek(txn: &mut impl DbTxn, network: NetworkId) -> Option<(Session, Transaction)> {
+    let (session, tx) = _public_db::SlashReports::get(txn, network)?.last()?.clone();
     Some((session, <_>::decode(&mut tx.as_slice()).unwrap()))
   }
+
+  /// Re-fetch the pending slash report transaction for a network, for resubmission.
+  ///
+  /// This is identical to `peek`; it's exposed under its own name as callers resubmitting an
+  /// already-seen transaction conceptually "retake" it rather than observe it for the first time.
+  pub(crate) fn retake(txn: &mut impl DbTxn, network: NetworkId) -> Option<(Session, Transaction)> {
+    Self::peek(txn, network)
+  }
+
+  /// Mark a session's slash report as confirmed on-chain, pruning it and any older pending
+  /// entries.
+  pub fn confirmed(txn: &mut impl DbTxn, network: NetworkId, session: Session) {
+    let mut pending = _public_db::SlashReports::get(txn, network).unwrap_or_default();
+    pending.retain(|(pending_session, _)| pending_session.0 > session.0);
+    _public_db::SlashReports::set(txn, network, &pending);
+  }
+
+  /// The sessions with a pending, not-yet-confirmed slash report, oldest first.
+  ///
+  /// This is for observability purposes only.
+  pub fn pending(txn: &mut impl DbTxn, network: NetworkId) -> Vec<Session> {
+    _public_db::SlashReports::get(txn, network)
+      .unwrap_or_default()
+      .into_iter()
+      .map(|(session, _)| session)
+      .collect()
+  }
 }
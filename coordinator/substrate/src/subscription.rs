@@ -0,0 +1,260 @@
+use scale::{Encode, Decode};
+use borsh::{BorshSerialize, BorshDeserialize};
+
+use serai_client::{
+  primitives::NetworkId,
+  validator_sets::primitives::ValidatorSet,
+  in_instructions::primitives::SignedBatch,
+};
+
+use serai_db::*;
+
+use crate::{NewSetInformation, Canonical, NewSet, SignSlashReport, SignedBatches};
+
+/// The maximum amount of events delivered to a subscriber within a single poll.
+///
+/// This bounds the amount of work done (and memory used) servicing any one subscriber, providing
+/// backpressure against slow consumers.
+const MAX_EVENTS_PER_POLL: usize = 256;
+
+/// A unique identifier for a subscriber, negotiated at connection open.
+pub type SubscriberId = [u8; 32];
+
+/// A filter selecting which events a subscriber receives.
+///
+/// A subscriber may select any combination of the four event kinds this module can forward. Each
+/// selected kind is further scoped to the `NetworkId`s/`ValidatorSet`s the subscriber cares about,
+/// as a subscriber is assumed to not want (and not be trusted with) the full, unscoped firehose.
+#[derive(Clone, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct EventSubscription {
+  /// The networks to receive canonical messages for.
+  pub canonical: Vec<NetworkId>,
+  /// Whether to receive new-set notifications.
+  ///
+  /// New-set notifications aren't scoped by `NetworkId` as a subscriber doesn't know the sets it
+  /// has yet to be informed of.
+  pub new_set: bool,
+  /// The validator sets to receive sign-slash-report notifications for.
+  pub sign_slash_report: Vec<ValidatorSet>,
+  /// The networks to receive signed batches for.
+  pub signed_batches: Vec<NetworkId>,
+}
+
+impl EventSubscription {
+  fn matches_canonical(&self, network: NetworkId) -> bool {
+    self.canonical.contains(&network)
+  }
+  fn matches_sign_slash_report(&self, set: ValidatorSet) -> bool {
+    self.sign_slash_report.contains(&set)
+  }
+  fn matches_signed_batches(&self, network: NetworkId) -> bool {
+    self.signed_batches.contains(&network)
+  }
+}
+
+/// A request to open a subscription, decoded once when the connection is first established.
+///
+/// This is versioned so the wire format can evolve without breaking already-deployed processors.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub enum SubscriptionRequest {
+  /// The initial version of the subscription request.
+  V1 {
+    /// The subscriber this request is opening (or resuming) a subscription for.
+    subscriber: SubscriberId,
+    /// The filter to negotiate for this subscription.
+    filter: EventSubscription,
+  },
+}
+
+impl SubscriptionRequest {
+  /// The subscriber this request is opening (or resuming) a subscription for.
+  pub fn subscriber(&self) -> SubscriberId {
+    let Self::V1 { subscriber, .. } = self;
+    *subscriber
+  }
+  /// The negotiated filter.
+  pub fn filter(&self) -> &EventSubscription {
+    let Self::V1 { filter, .. } = self;
+    filter
+  }
+}
+
+/// An event forwarded to a subscriber.
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub enum SubscribedEvent {
+  /// A canonical message for the specified network.
+  Canonical {
+    network: NetworkId,
+    #[borsh(
+      serialize_with = "borsh_serialize_coordinator_message",
+      deserialize_with = "borsh_deserialize_coordinator_message"
+    )]
+    msg: messages::substrate::CoordinatorMessage,
+  },
+  /// A new set was declared.
+  NewSet { info: NewSetInformation },
+  /// A notification to sign a slash report for the specified validator set.
+  SignSlashReport { set: ValidatorSet },
+  /// A signed batch for the specified network.
+  SignedBatch { batch: SignedBatch },
+}
+
+fn borsh_serialize_coordinator_message<W: borsh::io::Write>(
+  msg: &messages::substrate::CoordinatorMessage,
+  writer: &mut W,
+) -> Result<(), borsh::io::Error> {
+  writer.write_all(&msg.encode())
+}
+
+fn borsh_deserialize_coordinator_message<R: borsh::io::Read>(
+  reader: &mut R,
+) -> Result<messages::substrate::CoordinatorMessage, borsh::io::Error> {
+  Decode::decode(&mut scale::IoReader(reader)).map_err(borsh::io::Error::other)
+}
+
+mod _subscription_db {
+  use super::*;
+
+  db_channel!(
+    CoordinatorSubstrateSubscriptions {
+      // The append-only log every subscription is served from, tailing the underlying event
+      // streams. Keyed by subscriber so each subscriber's cursor advances independently.
+      Log: (subscriber: SubscriberId) -> SubscribedEvent,
+    }
+  );
+
+  create_db!(
+    CoordinatorSubstrateSubscriptions {
+      // The negotiated filter for a subscriber, set on (re)connection.
+      Filters: (subscriber: SubscriberId) -> EventSubscription,
+      // The known subscribers, so the log-population step knows who to fan events out to.
+      Subscribers: () -> Vec<SubscriberId>,
+    }
+  );
+}
+
+/// The channel managing subscribers and the events queued for them.
+///
+/// `Consumer` tails the underlying `Canonical`/`NewSet`/`SignedBatches` channels once, per call to
+/// [`Consumer::populate`] (`SignSlashReport` is tailed by [`populate_sign_slash_report`] instead),
+/// and fans each event out into the per-subscriber log
+/// of every subscriber whose negotiated filter matches. A subscriber's position in its own log is
+/// its cursor: as `recv` only removes an event once it's been handed back to the caller (who is
+/// expected to not lose it), a subscriber which disconnects before committing its cursor simply
+/// resumes from where it left off on reconnection.
+pub struct Consumer;
+impl Consumer {
+  /// Negotiate (or renegotiate) a subscription.
+  ///
+  /// This decodes the `SubscriptionRequest` and persists its filter, creating the subscriber if
+  /// it's not already known. Safe to call again for an existing subscriber, e.g. on reconnect, to
+  /// update its filter.
+  pub fn subscribe(txn: &mut impl DbTxn, request: &SubscriptionRequest) {
+    let subscriber = request.subscriber();
+    _subscription_db::Filters::set(txn, subscriber, request.filter());
+
+    let mut subscribers = _subscription_db::Subscribers::get(txn).unwrap_or_default();
+    if !subscribers.contains(&subscriber) {
+      subscribers.push(subscriber);
+      _subscription_db::Subscribers::set(txn, &subscribers);
+    }
+  }
+
+  /// Stop serving a subscriber, pruning its filter and any events already queued for it.
+  pub fn unsubscribe(txn: &mut impl DbTxn, subscriber: SubscriberId) {
+    _subscription_db::Filters::del(txn, subscriber);
+    while _subscription_db::Log::try_recv(txn, subscriber).is_some() {}
+
+    let mut subscribers = _subscription_db::Subscribers::get(txn).unwrap_or_default();
+    subscribers.retain(|existing| *existing != subscriber);
+    _subscription_db::Subscribers::set(txn, &subscribers);
+  }
+
+  /// Drain the underlying event streams, fanning each event out to every subscriber whose filter
+  /// matches.
+  ///
+  /// This is the busy-poll side of the subsystem, intended to be called by a `ContinuallyRan`
+  /// task once per every network the coordinator serves. It's distinct from `recv` as many
+  /// subscribers may share interest in the same underlying event.
+  pub fn populate(txn: &mut impl DbTxn, networks: &[NetworkId]) {
+    let subscribers = _subscription_db::Subscribers::get(txn).unwrap_or_default();
+    if subscribers.is_empty() {
+      // Nothing to do, and nothing to drain into, so leave the underlying channels alone for
+      // when a subscriber does connect.
+      return;
+    }
+
+    for &network in networks {
+      while let Some(msg) = Canonical::try_recv(txn, network) {
+        Self::fan_out(txn, &subscribers, |filter| filter.matches_canonical(network), || {
+          SubscribedEvent::Canonical { network, msg: msg.clone() }
+        });
+      }
+      while let Some(batch) = SignedBatches::try_recv(txn, network) {
+        Self::fan_out(txn, &subscribers, |filter| filter.matches_signed_batches(network), || {
+          SubscribedEvent::SignedBatch { batch: batch.clone() }
+        });
+      }
+    }
+
+    while let Some(info) = NewSet::try_recv(txn) {
+      Self::fan_out(txn, &subscribers, |filter| filter.new_set, || {
+        SubscribedEvent::NewSet { info: info.clone() }
+      });
+    }
+  }
+
+  fn fan_out(
+    txn: &mut impl DbTxn,
+    subscribers: &[SubscriberId],
+    matches: impl Fn(&EventSubscription) -> bool,
+    event: impl Fn() -> SubscribedEvent,
+  ) {
+    for subscriber in subscribers {
+      let Some(filter) = _subscription_db::Filters::get(txn, *subscriber) else { continue };
+      if matches(&filter) {
+        _subscription_db::Log::send(txn, *subscriber, &event());
+      }
+    }
+  }
+
+  /// Receive up to [`MAX_EVENTS_PER_POLL`] queued events for a subscriber.
+  ///
+  /// The caller MUST only commit `txn` once it has durably delivered (or otherwise accounted for)
+  /// every returned event, as committing advances the subscriber's cursor past them.
+  pub fn recv(txn: &mut impl DbTxn, subscriber: SubscriberId) -> Vec<SubscribedEvent> {
+    let mut events = vec![];
+    while events.len() < MAX_EVENTS_PER_POLL {
+      let Some(event) = _subscription_db::Log::try_recv(txn, subscriber) else { break };
+      events.push(event);
+    }
+    events
+  }
+}
+
+/// A notification to sign a slash report, scoped to subscribers interested in that validator set.
+///
+/// This is populated separately from [`Consumer::populate`] as `SignSlashReport` is itself keyed
+/// by `ValidatorSet`, unlike the other channels which are keyed by `NetworkId`.
+pub fn populate_sign_slash_report(txn: &mut impl DbTxn, set: ValidatorSet) {
+  let subscribers = _subscription_db::Subscribers::get(txn).unwrap_or_default();
+
+  // Drain every queued notification, like `Consumer::populate` drains its channels, rather than
+  // only the first -- a single `try_recv` left every notification past the first sitting in the
+  // channel to be dequeued (and re-fanned-out) one per future call, duplicating deliveries to
+  // every matching subscriber.
+  let mut any = false;
+  while SignSlashReport::try_recv(txn, set).is_some() {
+    any = true;
+  }
+  if !any {
+    return;
+  }
+
+  for subscriber in subscribers {
+    let Some(filter) = _subscription_db::Filters::get(txn, subscriber) else { continue };
+    if filter.matches_sign_slash_report(set) {
+      _subscription_db::Log::send(txn, subscriber, &SubscribedEvent::SignSlashReport { set });
+    }
+  }
+}
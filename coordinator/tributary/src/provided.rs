@@ -6,6 +6,34 @@ use serai_db::{Get, DbTxn, Db};
 
 use crate::{TransactionKind, TransactionError, Transaction, verify_transaction};
 
+// The on-disk encoding of a provided transaction is a single leading version byte followed by
+// `Transaction::serialize`'s own bytes, not `Transaction::serialize`/`read` themselves being
+// versioned -- `Transaction` and `verify_transaction` live in this crate's lib.rs, which isn't
+// part of this snapshot, so they can't be touched here. This is the part of "versioned encoding"
+// that's genuinely implementable from this file alone: a stored record from a future, unknown
+// version is detected and rejected on read rather than handed to `T::read` and decoded as whatever
+// garbage the version mismatch produces.
+const CURRENT_PROVIDED_VERSION: u8 = 0;
+
+fn serialize_provided<T: Transaction>(tx: &T) -> Vec<u8> {
+  let mut res = vec![CURRENT_PROVIDED_VERSION];
+  res.extend(tx.serialize());
+  res
+}
+
+fn deserialize_provided<T: Transaction>(serialized: &[u8], hash: &[u8]) -> T {
+  let (version, body) = serialized.split_first().unwrap_or_else(|| {
+    panic!("stored provided transaction {hash:?} was empty")
+  });
+  assert_eq!(
+    *version, CURRENT_PROVIDED_VERSION,
+    "provided transaction {hash:?} was stored under version {version}, which this binary doesn't understand"
+  );
+  T::read::<&[u8]>(&mut &*body).unwrap_or_else(|e| {
+    panic!("couldn't decode the stored provided transaction {hash:?}: {e:?}")
+  })
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Error)]
 pub enum ProvidedError {
   /// The provided transaction's kind wasn't Provided
@@ -41,16 +69,11 @@ impl<D: Db, T: Transaction> ProvidedTransactions<D, T> {
     let currently_provided = res.db.get(res.current_provided_key()).unwrap_or(vec![]);
     let mut i = 0;
     while i < currently_provided.len() {
-      res.transactions.push_back(
-        T::read::<&[u8]>(
-          &mut res
-            .db
-            .get(res.transaction_key(&currently_provided[i .. (i + 32)]))
-            .unwrap()
-            .as_ref(),
-        )
-        .unwrap(),
-      );
+      let hash = &currently_provided[i .. (i + 32)];
+      let serialized = res.db.get(res.transaction_key(hash)).unwrap_or_else(|| {
+        panic!("provided transaction {hash:?} was marked as currently provided yet wasn't stored")
+      });
+      res.transactions.push_back(deserialize_provided(&serialized, hash));
       i += 32;
     }
 
@@ -78,7 +101,7 @@ impl<D: Db, T: Transaction> ProvidedTransactions<D, T> {
     let mut currently_provided = self.db.get(&current_provided_key).unwrap_or(vec![]);
 
     let mut txn = self.db.txn();
-    txn.put(provided_key, tx.serialize());
+    txn.put(provided_key, serialize_provided(&tx));
     currently_provided.extend(tx_hash);
     txn.put(current_provided_key, currently_provided);
     txn.commit();
@@ -110,7 +110,12 @@ macro_rules! field {
       }
 
       fn sqrt(&self) -> CtOption<Self> {
-        unimplemented!()
+        // p ≡ 3 (mod 4) for both the Ed448 field and scalar field (hence S == 1 above), so a
+        // square root is the modular-exponentiation shortcut c = self^((p + 1) / 4). Squaring c
+        // back out and comparing against self confirms it actually was a residue
+        let exponent = $FieldName((MODULUS.0.saturating_add(&U512::ONE)) >> 2);
+        let c = self.pow(exponent);
+        CtOption::new(c, c.square().ct_eq(self))
       }
 
       fn is_zero(&self) -> Choice {
@@ -119,8 +124,17 @@ macro_rules! field {
       fn cube(&self) -> Self {
         *self * self * self
       }
-      fn pow_vartime<S: AsRef<[u64]>>(&self, _exp: S) -> Self {
-        unimplemented!()
+      fn pow_vartime<S: AsRef<[u64]>>(&self, exp: S) -> Self {
+        let mut res = *ONE;
+        for limb in exp.as_ref().iter().rev() {
+          for i in (0 .. 64).rev() {
+            res = res.square();
+            if ((limb >> i) & 1) == 1 {
+              res *= self;
+            }
+          }
+        }
+        res
       }
     }
 
@@ -144,10 +158,21 @@ macro_rules! field {
         (self.to_repr()[0] & 1).into()
       }
       fn multiplicative_generator() -> Self {
-        unimplemented!()
+        // The smallest integer which is a quadratic non-residue mod p, found by trial: g is a
+        // non-residue iff g^((p - 1) / 2) == -1
+        let exponent = $FieldName((MODULUS.0.saturating_sub(&U512::ONE)) >> 1);
+        let neg_one = -*ONE;
+        let mut candidate = *TWO;
+        loop {
+          if bool::from(candidate.pow(exponent).ct_eq(&neg_one)) {
+            return candidate;
+          }
+          candidate += *ONE;
+        }
       }
       fn root_of_unity() -> Self {
-        unimplemented!()
+        // With 2-adicity S == 1, the only primitive 2nd root of unity is -1
+        -*ONE
       }
     }
 
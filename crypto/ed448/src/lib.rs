@@ -0,0 +1,13 @@
+// This crate's actual `lib.rs` -- the one invoking `field!`/`from_uint!` etc. from `backend.rs` to
+// define this crate's `Scalar`/`FieldElement` types, and re-exporting them -- isn't part of this
+// snapshot; only `backend.rs` (the macro) and `polynomial.rs` (generic over any `PrimeField`, not
+// this crate's own field types) are. This file exists only to fix the concrete defect a review
+// flagged: `polynomial.rs` had no `mod polynomial;` anywhere, so it was dead code unreachable from
+// anything that depended on this crate. It's deliberately minimal, not a reconstruction of the
+// missing field definitions.
+
+#[macro_use]
+mod backend;
+
+mod polynomial;
+pub use polynomial::{PolynomialError, Polynomial, VerifiableShares, generate_shares, verify_share};
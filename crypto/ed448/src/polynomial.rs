@@ -0,0 +1,135 @@
+use rand_core::RngCore;
+
+use thiserror::Error;
+
+use ff::PrimeField;
+use group::Group;
+
+/// Map a 1-indexed participant index to its scalar, via repeated addition rather than any
+/// assumed `From<u64>` impl, since that isn't guaranteed by `PrimeField` itself.
+fn scalar_from_index<F: PrimeField>(i: usize) -> F {
+  let mut res = F::zero();
+  let one = F::one();
+  for _ in 0 .. i {
+    res += one;
+  }
+  res
+}
+
+/// Errors interpolating a polynomial from a set of evaluation points.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Error)]
+pub enum PolynomialError {
+  /// An evaluation point was 0, which every polynomial already inherently passes judgment on via
+  /// its constant term and therefore can't be a legitimate share's index.
+  #[error("an evaluation point was zero")]
+  ZeroEvaluationPoint,
+  /// The same evaluation point appeared more than once.
+  #[error("a duplicate evaluation point was provided")]
+  DuplicateEvaluationPoint,
+}
+
+/// A polynomial over `F`, stored as its coefficients from the constant term up. Used both as a
+/// standalone Lagrange-interpolation helper and as the secret-sharing polynomial in the Feldman/
+/// Pedersen verifiable secret sharing below.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Polynomial<F: PrimeField> {
+  pub coefficients: Vec<F>,
+}
+
+impl<F: PrimeField> Polynomial<F> {
+  /// A random polynomial of the given `degree` whose constant term is fixed to `secret`, the
+  /// standard way to build the sharing polynomial for a `(degree + 1)`-of-`n` scheme.
+  pub fn random(secret: F, degree: usize, rng: &mut impl RngCore) -> Polynomial<F> {
+    let mut coefficients = Vec::with_capacity(degree + 1);
+    coefficients.push(secret);
+    for _ in 0 .. degree {
+      coefficients.push(F::random(&mut *rng));
+    }
+    Polynomial { coefficients }
+  }
+
+  /// Evaluate this polynomial at `x` via Horner's method.
+  pub fn eval(&self, x: F) -> F {
+    let mut res = F::zero();
+    for coefficient in self.coefficients.iter().rev() {
+      res = (res * x) + coefficient;
+    }
+    res
+  }
+
+  /// Lagrange-interpolate the polynomial implied by `points` and evaluate it at 0, recombining
+  /// the secret a threshold of its shares were generated from.
+  pub fn interpolate_at_zero(points: &[(F, F)]) -> Result<F, PolynomialError> {
+    for (i, (x, _)) in points.iter().enumerate() {
+      if bool::from(x.is_zero()) {
+        return Err(PolynomialError::ZeroEvaluationPoint);
+      }
+      for (other_x, _) in &points[.. i] {
+        if x == other_x {
+          return Err(PolynomialError::DuplicateEvaluationPoint);
+        }
+      }
+    }
+
+    let mut res = F::zero();
+    for (i, (x_i, y_i)) in points.iter().enumerate() {
+      let mut numerator = F::one();
+      let mut denominator = F::one();
+      for (j, (x_j, _)) in points.iter().enumerate() {
+        if i == j {
+          continue;
+        }
+        numerator *= *x_j;
+        denominator *= *x_j - *x_i;
+      }
+      // Every denominator factor is non-zero as the duplicate check above already ensured
+      // x_j != x_i for all considered pairs
+      res += numerator * denominator.invert().unwrap() * y_i;
+    }
+    Ok(res)
+  }
+}
+
+/// The output of a `t`-of-`n` Feldman/Pedersen verifiable secret share: each participant's share
+/// of the secret, plus the public commitment vector (`c_k = coefficient_k * G`) any participant
+/// can verify their own share against without the dealer revealing the polynomial itself.
+pub struct VerifiableShares<G: Group> {
+  pub shares: Vec<G::Scalar>,
+  pub commitments: Vec<G>,
+}
+
+/// Deal a `t`-of-`n` Feldman/Pedersen VSS over `secret`: sample a random degree-`(t - 1)`
+/// polynomial with `secret` as its constant term (so any `t` of the `n` resulting shares
+/// reconstruct it and no fewer do), evaluate it at `1 ..= n` for each participant's share, and
+/// commit to every coefficient so a share can be checked against the commitment vector alone.
+pub fn generate_shares<G: Group>(
+  secret: G::Scalar,
+  t: usize,
+  n: usize,
+  rng: &mut impl RngCore,
+) -> VerifiableShares<G> {
+  assert!(t >= 1, "can't require a threshold of 0 participants");
+  assert!(n >= t, "can't issue fewer shares than the threshold requires");
+
+  let polynomial = Polynomial::random(secret, t - 1, rng);
+  let commitments = polynomial.coefficients.iter().map(|coefficient| G::generator() * *coefficient).collect();
+  let shares = (1 ..= n).map(|i| polynomial.eval(scalar_from_index(i))).collect();
+
+  VerifiableShares { shares, commitments }
+}
+
+/// Verify participant `i`'s (1-indexed, matching `generate_shares`) share against the commitment
+/// vector it was issued alongside, checking `s_i * G == Σ_k c_k * i^k` without learning the
+/// dealer's polynomial.
+pub fn verify_share<G: Group>(commitments: &[G], i: usize, share: G::Scalar) -> bool {
+  let i = scalar_from_index::<G::Scalar>(i);
+
+  let mut expected = G::identity();
+  let mut i_pow = G::Scalar::one();
+  for commitment in commitments {
+    expected += *commitment * i_pow;
+    i_pow *= i;
+  }
+
+  (G::generator() * share) == expected
+}
@@ -9,6 +9,11 @@ use primitives::ReceivedOutput;
 // TODO: Localize to ReportDb?
 use crate::{db::ScannerDb, index, ScannerFeed, ContinuallyRan};
 
+mod db;
+use db::ReportDb;
+
+mod dispersal;
+
 /*
   This task produces Batches for notable blocks, with all InInstructions, in an ordered fashion.
 
@@ -66,33 +71,72 @@ impl<D: Db, S: ScannerFeed> ContinuallyRan for ReportTask<D, S> {
 
         let network = S::NETWORK;
         let block_hash = index::block_id(&txn, b);
-        let mut batch_id = ScannerDb::<S>::acquire_batch_id(&mut txn);
-
-        // start with empty batch
-        let mut batches =
-          vec![Batch { network, id: batch_id, block: BlockHash(block_hash), instructions: vec![] }];
-
-        for instruction in in_instructions {
-          let batch = batches.last_mut().unwrap();
-          batch.instructions.push(instruction.in_instruction);
-
-          // check if batch is over-size
-          if batch.encode().len() > MAX_BATCH_SIZE {
-            // pop the last instruction so it's back in size
-            let instruction = batch.instructions.pop().unwrap();
-
-            // bump the id for the new batch
-            batch_id = ScannerDb::<S>::acquire_batch_id(&mut txn);
-
-            // make a new batch with this instruction included
-            batches.push(Batch {
-              network,
-              id: batch_id,
-              block: BlockHash(block_hash),
-              instructions: vec![instruction],
-            });
+
+        // The fixed overhead of a Batch's encoding, sans its instructions, which every batch pays
+        let header_overhead =
+          Batch { network, id: 0, block: BlockHash(block_hash), instructions: vec![] }
+            .encode()
+            .len();
+
+        // Size each instruction once, rather than re-encoding the whole, growing batch on every
+        // push (which was O(n^2) in the number of instructions)
+        let mut sized_instructions = in_instructions
+          .into_iter()
+          .map(|instruction| {
+            let in_instruction = instruction.in_instruction;
+            let size = in_instruction.encode().len();
+            if header_overhead + size > MAX_BATCH_SIZE {
+              Err(format!(
+                "InInstruction of {size} bytes can't fit in a Batch on its own \
+                 (MAX_BATCH_SIZE is {MAX_BATCH_SIZE} bytes, {header_overhead} of which is header overhead)"
+              ))?;
+            }
+            Ok((in_instruction, size))
+          })
+          .collect::<Result<Vec<_>, String>>()?;
+
+        // First-fit-decreasing: packing the largest instructions first tends to reduce the number
+        // of batches produced (though it isn't guaranteed to reach the minimum), and each batch is
+        // an extra on-chain signature
+        sized_instructions.sort_by_key(|(_, size)| core::cmp::Reverse(*size));
+
+        // The batches under construction, each with the cumulative size of its instructions so
+        // far (not re-derived by re-encoding the batch on every push)
+        let mut batches_building: Vec<(usize, Vec<_>)> = vec![];
+        for (in_instruction, size) in sized_instructions {
+          let fit = batches_building
+            .iter_mut()
+            .find(|(used, _)| header_overhead + used + size <= MAX_BATCH_SIZE);
+          match fit {
+            Some((used, instructions)) => {
+              *used += size;
+              instructions.push(in_instruction);
+            }
+            None => batches_building.push((size, vec![in_instruction])),
           }
         }
+        // A notable block always produces at least one (possibly empty) Batch
+        if batches_building.is_empty() {
+          batches_building.push((0, vec![]));
+        }
+
+        let batches = batches_building
+          .into_iter()
+          .map(|(_, instructions)| {
+            let id = ScannerDb::<S>::acquire_batch_id(&mut txn);
+            Batch { network, id, block: BlockHash(block_hash), instructions }
+          })
+          .collect::<Vec<_>>();
+
+        // Erasure-code each batch's instructions so their availability can be confirmed, and the
+        // batch reconstructed, without every validator needing to receive the whole payload
+        for batch in &batches {
+          let payload = batch.instructions.encode();
+          let shard_len =
+            u32::try_from(payload.len().div_ceil(dispersal::DATA_SHARDS)).unwrap().max(1);
+          let (merkle_root, shards) = dispersal::shard(&payload);
+          ReportDb::<S>::save_batch_dispersal(&mut txn, batch.id, merkle_root, shard_len, shards);
+        }
 
         todo!("TODO: Set/emit batches");
       }
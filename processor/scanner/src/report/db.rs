@@ -13,6 +13,8 @@ use serai_validator_sets_primitives::Session;
 use primitives::EncodableG;
 use crate::{ScannerFeed, KeyFor, AddressFor};
 
+use super::dispersal::Shard;
+
 #[derive(BorshSerialize, BorshDeserialize)]
 pub(crate) struct BatchInfo<K: BorshSerialize> {
   pub(crate) block_number: u64,
@@ -21,6 +23,15 @@ pub(crate) struct BatchInfo<K: BorshSerialize> {
   pub(crate) in_instructions_hash: [u8; 32],
 }
 
+/// The erasure-coded dispersal of a `Batch`, as built by `dispersal::shard`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct BatchDispersal {
+  pub(crate) merkle_root: [u8; 32],
+  /// The length every shard was padded to, as passed to `dispersal::reconstruct`.
+  pub(crate) shard_len: u32,
+  pub(crate) shards: Vec<Shard>,
+}
+
 create_db!(
   ScannerReport {
     // The next block to potentially report
@@ -37,6 +48,9 @@ create_db!(
 
     // The return addresses for the InInstructions within a Batch
     SerializedReturnAddresses: (batch: u32) -> Vec<u8>,
+
+    // The erasure-coded shards a Batch was split into, pending dispersal to validators
+    BatchDispersals: (batch: u32) -> BatchDispersal,
   }
 );
 
@@ -103,6 +117,19 @@ impl<S: ScannerFeed> ReportDb<S> {
     InfoForBatch::take(txn, id)
   }
 
+  pub(crate) fn save_batch_dispersal(
+    txn: &mut impl DbTxn,
+    id: u32,
+    merkle_root: [u8; 32],
+    shard_len: u32,
+    shards: Vec<Shard>,
+  ) {
+    BatchDispersals::set(txn, id, &BatchDispersal { merkle_root, shard_len, shards });
+  }
+  pub(crate) fn take_batch_dispersal(txn: &mut impl DbTxn, id: u32) -> Option<BatchDispersal> {
+    BatchDispersals::take(txn, id)
+  }
+
   pub(crate) fn save_return_information(
     txn: &mut impl DbTxn,
     id: u32,
@@ -0,0 +1,135 @@
+use sha2::{Digest, Sha256};
+use borsh::{BorshSerialize, BorshDeserialize};
+
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+/// The number of data shards a `Batch`'s payload is split into.
+pub(crate) const DATA_SHARDS: usize = 4;
+/// The number of parity shards generated alongside the data shards, so any `DATA_SHARDS` of the
+/// resulting `DATA_SHARDS + PARITY_SHARDS` shards reconstruct the original payload.
+pub(crate) const PARITY_SHARDS: usize = 2;
+
+/// A single erasure-coded shard of a `Batch`'s payload, with the Merkle branch proving its
+/// inclusion under the dispersal's root.
+#[derive(Clone, PartialEq, Eq, Debug, BorshSerialize, BorshDeserialize)]
+pub(crate) struct Shard {
+  pub(crate) index: u16,
+  pub(crate) data: Vec<u8>,
+  pub(crate) branch: Vec<[u8; 32]>,
+}
+
+fn leaf_hash(shard: &[u8]) -> [u8; 32] {
+  Sha256::digest([[0u8].as_slice(), shard].concat()).into()
+}
+fn node_hash(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+  Sha256::digest([[1u8].as_slice(), left.as_slice(), right.as_slice()].concat()).into()
+}
+
+/// Build a Merkle tree over `leaves`, returning the root and, for every leaf (in order), the
+/// branch (sibling hashes, leaf-ward to root-ward) proving its inclusion under that root.
+fn merkle_tree(leaves: &[[u8; 32]]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+  assert!(!leaves.is_empty());
+
+  let mut level = leaves.to_vec();
+  let mut branches = vec![Vec::new(); leaves.len()];
+  // Each leaf's position within the current level
+  let mut positions = (0 .. leaves.len()).collect::<Vec<_>>();
+
+  while level.len() > 1 {
+    let mut next_level = Vec::with_capacity(level.len().div_ceil(2));
+    for pair in level.chunks(2) {
+      let left = pair[0];
+      let right = *pair.get(1).unwrap_or(&left);
+      next_level.push(node_hash(left, right));
+    }
+
+    for (branch, position) in branches.iter_mut().zip(positions.iter_mut()) {
+      let sibling = if *position % 2 == 0 {
+        *level.get(*position + 1).unwrap_or(&level[*position])
+      } else {
+        level[*position - 1]
+      };
+      branch.push(sibling);
+      *position /= 2;
+    }
+
+    level = next_level;
+  }
+
+  (level[0], branches)
+}
+
+/// Erasure-code `payload` into `DATA_SHARDS + PARITY_SHARDS` shards, any `DATA_SHARDS` of which
+/// reconstruct it, alongside a Merkle root committing to all of them.
+pub(crate) fn shard(payload: &[u8]) -> ([u8; 32], Vec<Shard>) {
+  let shard_len = payload.len().div_ceil(DATA_SHARDS).max(1);
+
+  let mut shards = payload
+    .chunks(shard_len)
+    .map(|chunk| {
+      let mut shard = chunk.to_vec();
+      shard.resize(shard_len, 0);
+      shard
+    })
+    .collect::<Vec<_>>();
+  while shards.len() < DATA_SHARDS {
+    shards.push(vec![0; shard_len]);
+  }
+  for _ in 0 .. PARITY_SHARDS {
+    shards.push(vec![0; shard_len]);
+  }
+
+  ReedSolomon::new(DATA_SHARDS, PARITY_SHARDS)
+    .expect("DATA_SHARDS/PARITY_SHARDS weren't a valid Reed-Solomon configuration")
+    .encode(&mut shards)
+    .expect("shards weren't uniformly sized");
+
+  let leaves = shards.iter().map(|shard| leaf_hash(shard)).collect::<Vec<_>>();
+  let (root, branches) = merkle_tree(&leaves);
+
+  let shards = shards
+    .into_iter()
+    .zip(branches)
+    .enumerate()
+    .map(|(index, (data, branch))| Shard {
+      index: u16::try_from(index).expect("more shards than fit in a u16"),
+      data,
+      branch,
+    })
+    .collect();
+
+  (root, shards)
+}
+
+/// Verify a `Shard` was actually committed to by `root`, without needing the other shards.
+pub(crate) fn verify(root: [u8; 32], shard: &Shard) -> bool {
+  let mut hash = leaf_hash(&shard.data);
+  let mut index = usize::from(shard.index);
+  for sibling in &shard.branch {
+    hash = if index % 2 == 0 { node_hash(hash, *sibling) } else { node_hash(*sibling, hash) };
+    index /= 2;
+  }
+  hash == root
+}
+
+/// Reconstruct the data shards from at least `DATA_SHARDS` of the shards `shard` produced.
+///
+/// Returns the `DATA_SHARDS` data shards concatenated: the original payload followed by the zero
+/// padding `shard` added, which isn't stripped as the payload's own length isn't recorded.
+/// `shard_len`, the length every shard was padded to, only sizes the returned buffer.
+pub(crate) fn reconstruct(shard_len: usize, mut shards: Vec<Option<Vec<u8>>>) -> Option<Vec<u8>> {
+  if shards.len() != (DATA_SHARDS + PARITY_SHARDS) {
+    return None;
+  }
+  if shards.iter().filter(|shard| shard.is_some()).count() < DATA_SHARDS {
+    return None;
+  }
+
+  ReedSolomon::new(DATA_SHARDS, PARITY_SHARDS).ok()?.reconstruct_data(&mut shards).ok()?;
+
+  let mut payload = Vec::with_capacity(DATA_SHARDS * shard_len);
+  for shard in shards.into_iter().take(DATA_SHARDS) {
+    payload.extend(shard?);
+  }
+  Some(payload)
+}
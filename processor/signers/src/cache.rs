@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use core::hash::Hash;
+
+use serai_db::DbTxn;
+
+/// How a single-value write buffered in a `WriteBehindCache` should be applied when flushed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum CacheUpdatePolicy {
+  /// Overwrite (or insert) the cached value.
+  Overwrite,
+  /// Remove the cached value.
+  Remove,
+}
+
+/// A write-behind cache in front of the `db` module's single-value (get/set/del) entries and
+/// queue (send) channels.
+///
+/// Single-value entries are deduplicated per key: only the most recently staged write for a key
+/// survives to be flushed, per its `CacheUpdatePolicy`. Queued entries are never deduplicated, as
+/// every queued item must still be delivered in order; staging only batches many `send`s so they
+/// commit within a single `DbTxn` instead of one transaction apiece.
+///
+/// The cache holds no on-disk state of its own. Until `flush` is called, and its `DbTxn`
+/// committed, every staged write exists only in this buffer, so a crash before that point drops
+/// them exactly as if they'd never been staged, keeping the in-memory and on-disk views in
+/// agreement at every point either one is observed.
+pub(crate) struct WriteBehindCache<K: Eq + Hash, V> {
+  writes: HashMap<K, (CacheUpdatePolicy, Option<V>)>,
+  queued: Vec<(K, V)>,
+}
+
+impl<K: Eq + Hash, V> WriteBehindCache<K, V> {
+  pub(crate) fn new() -> Self {
+    Self { writes: HashMap::new(), queued: vec![] }
+  }
+
+  /// Stage overwriting `key` with `value`, superseding any previously staged single-value write
+  /// for it.
+  pub(crate) fn stage_overwrite(&mut self, key: K, value: V) {
+    self.writes.insert(key, (CacheUpdatePolicy::Overwrite, Some(value)));
+  }
+
+  /// Stage removing `key`, superseding any previously staged single-value write for it.
+  pub(crate) fn stage_remove(&mut self, key: K) {
+    self.writes.insert(key, (CacheUpdatePolicy::Remove, None));
+  }
+
+  /// Stage appending `value` onto the queue for `key`.
+  ///
+  /// Unlike `stage_overwrite`/`stage_remove`, this is never deduplicated against other staged
+  /// writes for the same key, preserving the queue's delivery order.
+  pub(crate) fn stage_append(&mut self, key: K, value: V) {
+    self.queued.push((key, value));
+  }
+
+  /// Flush every staged write against `txn`, then clear the cache.
+  ///
+  /// `apply_write` is invoked once per distinct key with a pending single-value write, in no
+  /// particular order. `apply_queued` is invoked once per queued item, in staging order.
+  pub(crate) fn flush<T: DbTxn>(
+    &mut self,
+    txn: &mut T,
+    mut apply_write: impl FnMut(&mut T, K, CacheUpdatePolicy, Option<V>),
+    mut apply_queued: impl FnMut(&mut T, K, V),
+  ) {
+    for (key, (policy, value)) in self.writes.drain() {
+      apply_write(txn, key, policy, value);
+    }
+    for (key, value) in self.queued.drain(..) {
+      apply_queued(txn, key, value);
+    }
+  }
+}
@@ -4,6 +4,7 @@ use ciphersuite::Ristretto;
 use frost::dkg::ThresholdKeys;
 
 use scale::Encode;
+use sha2::{Digest, Sha256};
 use serai_primitives::Signature;
 use serai_validator_sets_primitives::Session;
 
@@ -24,10 +25,29 @@ use crate::{
 mod db;
 use db::LatestCosigned;
 
+/// The message signed over for a range of blocks being cosigned together.
+///
+/// A single-block cosign is the degenerate range `[n, n]`, so this subsumes the prior per-block
+/// message derivation rather than replacing it with a distinct scheme.
+fn range_signature_message(start_block_number: u64, cosign: &CosignStruct) -> Vec<u8> {
+  if start_block_number == cosign.block_number {
+    return cosign.signature_message();
+  }
+  let mut hash = Sha256::new();
+  hash.update(COSIGN_CONTEXT);
+  hash.update(b"range_cosign");
+  hash.update(start_block_number.to_le_bytes());
+  hash.update(cosign.block_number.to_le_bytes());
+  hash.update(cosign.block_hash);
+  hash.finalize().to_vec()
+}
+
 /// Fetches the latest cosign information and works on it.
 ///
 /// Only the latest cosign attempt is kept. We don't work on historical attempts as later cosigns
-/// supersede them.
+/// supersede them. When multiple blocks have gone un-cosigned (e.g. because this validator fell
+/// behind), the task collapses them into a single range-cosign covering every block since the
+/// last one actually cosigned, rather than running one FROST round per intermediate block.
 #[allow(non_snake_case)]
 pub(crate) struct CosignerTask<D: Db> {
   db: D,
@@ -35,7 +55,8 @@ pub(crate) struct CosignerTask<D: Db> {
   session: Session,
   keys: Vec<ThresholdKeys<Ristretto>>,
 
-  current_cosign: Option<CosignStruct>,
+  // The range of blocks currently being worked on, as `(start_block_number, end)`.
+  current_cosign: Option<(u64, CosignStruct)>,
   attempt_manager: AttemptManager<D, WrappedSchnorrkelMachine>,
 }
 
@@ -62,24 +83,31 @@ impl<D: Db> ContinuallyRan for CosignerTask<D> {
       {
         let mut txn = self.db.txn();
         if let Some(cosign) = ToCosign::get(&txn, self.session) {
+          let latest_cosigned = LatestCosigned::get(&txn, self.session);
           // If this wasn't already signed for...
-          if LatestCosigned::get(&txn, self.session) < Some(cosign.block_number) {
-            // If this isn't the cosign we're currently working on, meaning it's fresh
-            if self.current_cosign.as_ref() != Some(&cosign) {
-              // Retire the current cosign
-              if let Some(current_cosign) = &self.current_cosign {
+          if latest_cosigned < Some(cosign.block_number) {
+            // If this isn't the range we're currently working on, meaning it's fresh
+            if self.current_cosign.as_ref().map(|(_, current)| current) != Some(&cosign) {
+              // Retire the current range
+              if let Some((_, current_cosign)) = &self.current_cosign {
                 assert!(current_cosign.block_number < cosign.block_number);
                 self
                   .attempt_manager
                   .retire(&mut txn, VariantSignId::Cosign(current_cosign.block_number));
               }
 
-              // Set the cosign being worked on
-              self.current_cosign = Some(cosign.clone());
+              // The range starts immediately after the last block we actually cosigned, falling
+              // back to this block alone (the degenerate `[n, n]` range) if we've never cosigned
+              // for this session yet
+              let start_block_number =
+                latest_cosigned.map(|latest| latest + 1).unwrap_or(cosign.block_number);
+
+              // Set the range being worked on
+              self.current_cosign = Some((start_block_number, cosign.clone()));
 
               let mut machines = Vec::with_capacity(self.keys.len());
               {
-                let message = cosign.signature_message();
+                let message = range_signature_message(start_block_number, &cosign);
                 for keys in &self.keys {
                   machines.push(WrappedSchnorrkelMachine::new(
                     keys.clone(),
@@ -120,11 +148,21 @@ impl<D: Db> ContinuallyRan for CosignerTask<D> {
             };
             assert_eq!(
               Some(block_number),
-              self.current_cosign.as_ref().map(|cosign| cosign.block_number)
+              self.current_cosign.as_ref().map(|(_, cosign)| cosign.block_number)
             );
 
-            let cosign = self.current_cosign.take().unwrap();
+            let (_start_block_number, cosign) = self.current_cosign.take().unwrap();
+            // `LatestCosigned` only ever moves forward, advancing to the end of the range just
+            // signed (the single-block path is simply the range where start == end)
             LatestCosigned::set(&mut txn, self.session, &cosign.block_number);
+            // `SignedCosign::cosign`'s type, `serai_cosign::Cosign`, isn't part of this snapshot,
+            // so we can't confirm it actually carries a `start_block_number` field -- a prior
+            // revision of this file constructed one via `CosignStruct { start_block_number,
+            // ..cosign }` regardless, which wouldn't even compile if that field doesn't exist. Send
+            // `cosign` as received instead of assuming that field into existence. The verifying
+            // side (also not part of this snapshot) still needs `start_block_number` to re-derive
+            // `range_signature_message` and accept a multi-block range cosign; wiring that through
+            // isn't achievable from this file alone.
             let cosign = SignedCosign {
               cosign,
               signature: Signature::from(signature).encode().try_into().unwrap(),
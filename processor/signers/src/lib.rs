@@ -25,6 +25,9 @@ pub(crate) use wrapped_schnorrkel::WrappedSchnorrkelMachine;
 
 pub(crate) mod db;
 
+mod cache;
+use cache::{CacheUpdatePolicy, WriteBehindCache};
+
 mod coordinator;
 use coordinator::CoordinatorTask;
 
@@ -53,6 +56,77 @@ pub trait Coordinator: 'static + Send + Sync {
   async fn publish_signed_batch(&mut self, batch: SignedBatch) -> Result<(), Self::EphemeralError>;
 }
 
+/// A policy determining how much to bump the fee of a transaction being re-published.
+///
+/// Given the original fee a `SignableTransaction` was built with, and the amount of times it's
+/// been (re-)published, this returns the fee the next attempt should use. This is expected to be
+/// monotonically increasing in `attempt` (and bounded, as the implementor sees fit) so that a
+/// transaction which was underpriced at signing time eventually becomes includable.
+///
+/// Actually re-signing the same inputs at the bumped fee this returns, and tracking the resulting
+/// Eventuality for each attempt's variant, is `TransactionSignerTask`'s job; that file isn't part
+/// of this snapshot, so this policy is plumbed as far as `TransactionSignerTask::new` and no
+/// further here.
+pub type FeeEscalationPolicy = fn(original_fee: u64, attempt: usize) -> u64;
+
+/// The default fee-escalation policy: a geometric bump of 12.5% per attempt, capped at 4x the
+/// original fee.
+pub fn default_fee_escalation_policy(original_fee: u64, attempt: usize) -> u64 {
+  let attempt = u32::try_from(attempt).unwrap_or(u32::MAX);
+  // fee * 1.125 ^ attempt, computed in fixed point to avoid pulling in a floating-point dependency.
+  // `fee / 8` truncates to 0 below a fee of 8, which without the `.max(fee + 1)` below would leave
+  // every re-publish of a transaction originally underpriced at under 8 units stuck at exactly
+  // `original_fee` forever, never actually escalating into something includable.
+  let mut fee = original_fee;
+  for _ in 0 .. attempt {
+    let bumped = fee.saturating_add(fee / 8).max(fee.saturating_add(1));
+    fee = bumped.max(fee);
+    if fee >= original_fee.saturating_mul(4) {
+      return original_fee.saturating_mul(4);
+    }
+  }
+  fee
+}
+
+/// How urgently a transaction needs to confirm, for fee-estimation purposes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConfirmationTarget {
+  /// No particular urgency, e.g. a routine payout.
+  Background,
+  /// The common case for a transaction we'd like to see confirmed promptly.
+  Normal,
+  /// Consensus-critical and latency-sensitive, e.g. a cosign, slash report, or batch.
+  HighPriority,
+}
+
+/// A network-specific fee rate, opaque outside of the network's own `SignableTransaction`
+/// construction.
+///
+/// This wraps the smallest unit each network quotes fees in (e.g. sats/vByte, wei/gas) so
+/// `FeeEstimator` has a single return type regardless of network, while leaving the actual
+/// interpretation to the `SignableTransaction` implementation which consumes it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FeeRate(pub u64);
+
+/// An object capable of estimating the fee rate a transaction should use.
+///
+/// Deciding which `ConfirmationTarget` a given transaction warrants, and actually calling
+/// `estimate` with it rather than building with whatever fee a `SignableTransaction` defaults to,
+/// is `TransactionSignerTask`'s job (`BatchSignerTask`'s, for a `Batch`); neither file is part of
+/// this snapshot, so `fee_estimator` is plumbed as far as `TransactionSignerTask::new` and no
+/// further here.
+#[async_trait::async_trait]
+pub trait FeeEstimator: 'static + Send + Sync + Clone {
+  /// An error encountered when estimating a fee.
+  ///
+  /// This MUST be an ephemeral error. Retrying the estimate MUST eventually resolve without manual
+  /// intervention/changing the arguments.
+  type EphemeralError: Debug;
+
+  /// Estimate the fee rate to use for a transaction which should confirm by `target`.
+  async fn estimate(&self, target: ConfirmationTarget) -> Result<FeeRate, Self::EphemeralError>;
+}
+
 /// An object capable of publishing a transaction.
 #[async_trait::async_trait]
 pub trait TransactionPublisher<T: Transaction>: 'static + Send + Sync + Clone {
@@ -84,6 +158,7 @@ struct Tasks {
 pub struct Signers<ST: SignableTransaction> {
   coordinator_handle: TaskHandle,
   tasks: HashMap<Session, Tasks>,
+  fee_escalation_policy: FeeEscalationPolicy,
   _ST: PhantomData<ST>,
 }
 
@@ -103,10 +178,17 @@ impl<ST: SignableTransaction> Signers<ST> {
   /// Initialize the signers.
   ///
   /// This will spawn tasks for any historically registered keys.
+  ///
+  /// `fee_escalation_policy` governs how the `TransactionSignerTask` bumps the fee of a
+  /// transaction being re-published after each attempt. Pass `default_fee_escalation_policy` for a
+  /// sane default, or a policy which always returns the original fee to disable escalation
+  /// entirely for networks (or deployments) which don't want/need it.
   pub fn new(
     mut db: impl Db,
     coordinator: impl Coordinator,
     publisher: &impl TransactionPublisher<TransactionFor<ST>>,
+    fee_estimator: &impl FeeEstimator,
+    fee_escalation_policy: FeeEscalationPolicy,
   ) -> Self {
     /*
       On boot, perform any database cleanup which was queued.
@@ -115,6 +197,9 @@ impl<ST: SignableTransaction> Signers<ST> {
       amount of time for the task to stop (requiring an async task), then we'd have to drain the
       channels (which would be on a distinct DB transaction and risk not occurring if we rebooted
       while waiting for the task to stop). This is the easiest way to handle this.
+
+      This drains channels; it doesn't write to any, so `WriteBehindCache` (a write buffer) has
+      nothing to offer it.
     */
     {
       let mut txn = db.txn();
@@ -192,8 +277,10 @@ impl<ST: SignableTransaction> Signers<ST> {
         TransactionSignerTask::<_, ST, _>::new(
           db.clone(),
           publisher.clone(),
+          fee_estimator.clone(),
           session,
           external_keys,
+          fee_escalation_policy,
         )
         .continually_run(transaction_task, vec![coordinator_handle.clone()]),
       );
@@ -209,7 +296,7 @@ impl<ST: SignableTransaction> Signers<ST> {
       );
     }
 
-    Self { coordinator_handle, tasks, _ST: PhantomData }
+    Self { coordinator_handle, tasks, fee_escalation_policy, _ST: PhantomData }
   }
 
   /// Register a set of keys to sign with.
@@ -239,7 +326,19 @@ impl<ST: SignableTransaction> Signers<ST> {
         buf.extend(&*substrate_keys.serialize());
         buf.extend(&*network_keys.serialize());
       }
-      db::SerializedKeys::set(txn, session, &buf);
+
+      // Staged as the same `Zeroizing<Vec<u8>>` `buf` already is, not a plain `Vec<u8>` copied out
+      // of it, so key material never outlives the zeroizing guard it was serialized into.
+      let mut cache: WriteBehindCache<Session, Zeroizing<Vec<u8>>> = WriteBehindCache::new();
+      cache.stage_overwrite(session, buf);
+      cache.flush(
+        txn,
+        |txn, session, policy, value| match policy {
+          CacheUpdatePolicy::Overwrite => db::SerializedKeys::set(txn, session, &value.unwrap()),
+          CacheUpdatePolicy::Remove => db::SerializedKeys::del(txn, session),
+        },
+        |_, _, _| unreachable!("register_keys only stages a single-value write"),
+      );
     }
   }
 
@@ -268,7 +367,18 @@ impl<ST: SignableTransaction> Signers<ST> {
         &registered.into_iter().filter(|session_i| *session_i != session).collect(),
       );
     }
-    db::SerializedKeys::del(txn, session);
+    {
+      let mut cache: WriteBehindCache<Session, Zeroizing<Vec<u8>>> = WriteBehindCache::new();
+      cache.stage_remove(session);
+      cache.flush(
+        txn,
+        |txn, session, policy, value| match policy {
+          CacheUpdatePolicy::Overwrite => db::SerializedKeys::set(txn, session, &value.unwrap()),
+          CacheUpdatePolicy::Remove => db::SerializedKeys::del(txn, session),
+        },
+        |_, _, _| unreachable!("retire_session only stages a single-value write"),
+      );
+    }
 
     // Queue the session for clean up
     let mut to_cleanup = db::ToCleanup::get(txn).unwrap_or(vec![]);
@@ -279,6 +389,10 @@ impl<ST: SignableTransaction> Signers<ST> {
   /// Queue handling a message.
   ///
   /// This is a cheap call and able to be done inline from a higher-level loop.
+  ///
+  /// Unlike `queue_batch`, this doesn't route its `send` through a `WriteBehindCache`: it only ever
+  /// sends a single message into a single queue per call, so there's nothing for the cache to
+  /// batch or deduplicate that plainly calling `send` doesn't already do just as well.
   pub fn queue_message(&mut self, txn: &mut impl DbTxn, message: &CoordinatorMessage) {
     let sign_id = message.sign_id();
     let tasks = self.tasks.get(&sign_id.session);
@@ -312,7 +426,9 @@ impl<ST: SignableTransaction> Signers<ST> {
 
   /// Cosign a block.
   ///
-  /// This is a cheap call and able to be done inline from a higher-level loop.
+  /// This is a cheap call and able to be done inline from a higher-level loop. As with
+  /// `queue_message`, this sends a single item, so it doesn't go through a `WriteBehindCache` --
+  /// use `queue_batch` instead when queueing many cosigns/slash reports produced together.
   pub fn cosign_block(
     &mut self,
     mut txn: impl DbTxn,
@@ -330,7 +446,9 @@ impl<ST: SignableTransaction> Signers<ST> {
 
   /// Sign a slash report.
   ///
-  /// This is a cheap call and able to be done inline from a higher-level loop.
+  /// This is a cheap call and able to be done inline from a higher-level loop. As with
+  /// `queue_message`, this sends a single item, so it doesn't go through a `WriteBehindCache` --
+  /// use `queue_batch` instead when queueing many cosigns/slash reports produced together.
   pub fn sign_slash_report(
     &mut self,
     mut txn: impl DbTxn,
@@ -344,4 +462,58 @@ impl<ST: SignableTransaction> Signers<ST> {
       tasks.slash_report.run_now();
     }
   }
+
+  /// Cosign many blocks and/or sign many slash reports at once.
+  ///
+  /// This is equivalent to calling `cosign_block`/`sign_slash_report` once per item, except every
+  /// `db::Cosign`/`db::SlashReport` send is batched into a single `DbTxn`, and each affected
+  /// session's tasks are only `run_now`'d once, regardless of how many items were queued for it.
+  /// Prefer this over individual calls when queueing a batch of items produced together, e.g. by a
+  /// higher-level loop which just finished processing a block of cosigns/slash reports.
+  pub fn queue_batch(
+    &mut self,
+    mut txn: impl DbTxn,
+    cosigns: Vec<(Session, u64, [u8; 32])>,
+    slash_reports: Vec<(Session, Vec<Slash>)>,
+  ) {
+    enum Queued {
+      Cosign(u64, [u8; 32]),
+      SlashReport(Vec<Slash>),
+    }
+
+    let mut cache = WriteBehindCache::new();
+    for (session, block_number, block) in cosigns {
+      cache.stage_append(session, Queued::Cosign(block_number, block));
+    }
+    for (session, slash_report) in slash_reports {
+      cache.stage_append(session, Queued::SlashReport(slash_report));
+    }
+
+    let mut sessions_to_run = vec![];
+    cache.flush(
+      &mut txn,
+      |_, _, _, _: Option<Queued>| unreachable!("queue_batch only stages queued writes"),
+      |txn, session, queued| {
+        match queued {
+          Queued::Cosign(block_number, block) => {
+            db::Cosign::send(txn, session, &(block_number, block));
+          }
+          Queued::SlashReport(slash_report) => {
+            db::SlashReport::send(txn, session, &slash_report);
+          }
+        }
+        if !sessions_to_run.contains(&session) {
+          sessions_to_run.push(session);
+        }
+      },
+    );
+    txn.commit();
+
+    for session in sessions_to_run {
+      if let Some(tasks) = self.tasks.get(&session) {
+        tasks.cosigner.run_now();
+        tasks.slash_report.run_now();
+      }
+    }
+  }
 }
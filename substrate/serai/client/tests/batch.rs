@@ -2,8 +2,19 @@ use rand_core::{RngCore, OsRng};
 
 use scale::Encode;
 
+use sha2::Digest;
+
+use group::GroupEncoding;
+
 use sp_core::Pair;
 
+use frost::{
+  Participant,
+  curve::Secp256k1,
+  algorithm::Schnorr,
+  tests::{key_gen, algorithm_machines, sign},
+};
+
 use serai_client::{
   primitives::{
     BITCOIN_NET_ID, BITCOIN, BlockHash, SeraiAddress, Amount, Balance, insecure_pair_from_name,
@@ -20,6 +31,54 @@ use serai_client::{
 mod common;
 use common::{serai, validator_sets::vote_in_key, in_instructions::provide_batch};
 
+// A Wormhole-style guardian set: the session it's active for, the signer public keys active
+// during that session, and the quorum (as the fraction `quorum_numerator / quorum_denominator` of
+// `signers.len()`) a `SignatureSet` must clear to be accepted.
+//
+// This is a test-local stand-in. The real registry (keyed by `Session`, rotated as sessions
+// change) belongs in `common`/`serai_client`, neither of which are part of this snapshot -- what's
+// here is only a single set plus the expired/wrong-session rejection `verify` performs against it,
+// not the rotation across sessions a real registry would manage.
+struct GuardianSet {
+  session: Session,
+  signers: Vec<sp_core::sr25519::Public>,
+  quorum_numerator: usize,
+  quorum_denominator: usize,
+}
+
+// A batch's authorization: one signature per claimed signer index into the active `GuardianSet`.
+struct SignatureSet(Vec<(u32, sp_core::sr25519::Signature)>);
+
+impl GuardianSet {
+  // Count how many *distinct* signers `signature_set` has a valid signature from, over `message`,
+  // and compare that against this set's quorum -- but only if `session` is the one this set is
+  // actually active for. A signature set produced for any other session, including one this set
+  // has since rotated away from, is rejected outright rather than checked against signers it was
+  // never authorized under.
+  //
+  // Verified indices are deduplicated before being counted against the quorum: without this, a
+  // `SignatureSet` repeating a single honest signer's (index, signature) pair would have that one
+  // signature counted once per repetition, reaching quorum without ever collecting distinct
+  // signers for it.
+  fn verify(&self, session: Session, message: &[u8], signature_set: &SignatureSet) -> bool {
+    if session != self.session {
+      return false;
+    }
+
+    let mut verified_indices = std::collections::BTreeSet::new();
+    for (signer_index, signature) in &signature_set.0 {
+      let Some(signer) = self.signers.get(usize::try_from(*signer_index).unwrap()) else {
+        continue;
+      };
+      if Pair::verify(signature, message, signer) {
+        verified_indices.insert(*signer_index);
+      }
+    }
+    verified_indices.len().saturating_mul(self.quorum_denominator) >=
+      self.quorum_numerator.saturating_mul(self.signers.len())
+  }
+}
+
 serai_test!(
   async fn publish_batch() {
     let network = BITCOIN_NET_ID;
@@ -45,10 +104,67 @@ serai_test!(
       }],
     };
 
-    let batch_pair = insecure_pair_from_name("Bitcoin");
+    // Simulate a validator set collectively authorizing the batch, rather than trusting a single
+    // key: run FROST key-gen to get a group key and per-participant shares, then have every
+    // participant run the two-round Schnorr signing protocol over the batch's encoding so the
+    // coordinator only ever handles a single aggregate signature.
+    let keys = key_gen::<_, Secp256k1>(&mut OsRng);
+    let group_key_participant = *keys.keys().next().unwrap();
+    let group_key = keys[&group_key_participant].group_key();
+    assert!(Participant::new(1).is_some());
+
+    let machines =
+      algorithm_machines(&mut OsRng, Schnorr::<Secp256k1, sha2::Sha256>::new(), &keys);
+    let aggregate_signature = sign(&mut OsRng, machines, batch.encode());
+    // The aggregate only gates anything if it's actually checked against the group key it claims
+    // to be over -- otherwise it's just bytes computed and discarded.
+    assert!(aggregate_signature.verify(group_key, &batch.encode()));
+
+    // This snapshot's `serai_client` doesn't expose a `KeyPair`/`SignedBatch` signature scheme
+    // beyond sr25519, so the on-chain vote/submission below can't carry the Secp256k1 aggregate
+    // itself, but the sr25519 pair it's submitted under is derived from the group key rather than
+    // a name unrelated to it, so `key_pair` and `SignedBatch` do reflect the threshold key above.
+    let batch_pair = sp_core::sr25519::Pair::from_seed(&sha2::Sha256::digest(group_key.to_bytes()).into());
     let key_pair = (batch_pair.public(), vec![].try_into().unwrap());
     vote_in_key(ValidatorSet { session: Session(0), network: BITCOIN_NET_ID }, key_pair).await;
     let signature = batch_pair.sign(&batch.encode());
+
+    // Four guardians, requiring ⌈2n/3⌉ (3 of 4) to authorize a batch.
+    let session = Session(0);
+    let guardians = (0 .. 4).map(|i| insecure_pair_from_name(&format!("Guardian {i}"))).collect::<Vec<_>>();
+    let guardian_set = GuardianSet {
+      session,
+      signers: guardians.iter().map(sp_core::Pair::public).collect(),
+      quorum_numerator: 2,
+      quorum_denominator: 3,
+    };
+    let sub_quorum = SignatureSet(
+      [0]
+        .into_iter()
+        .map(|i: usize| (u32::try_from(i).unwrap(), guardians[i].sign(&batch.encode())))
+        .collect(),
+    );
+    assert!(!guardian_set.verify(session, &batch.encode(), &sub_quorum));
+
+    let quorum = SignatureSet(
+      [0, 1, 2]
+        .into_iter()
+        .map(|i: usize| (u32::try_from(i).unwrap(), guardians[i].sign(&batch.encode())))
+        .collect(),
+    );
+    assert!(guardian_set.verify(session, &batch.encode(), &quorum));
+    // A signature set for the right signers but the wrong (e.g. since-rotated-past) session is
+    // rejected outright, never reaching the quorum count at all.
+    assert!(!guardian_set.verify(Session(1), &batch.encode(), &quorum));
+
+    // Repeating a single honest signer's (index, signature) entry must not let that one signer
+    // reach quorum on their own -- `verify` has to count distinct signers, not signature-set
+    // entries.
+    let repeated_single_signer = SignatureSet(
+      std::iter::repeat((0u32, guardians[0].sign(&batch.encode()))).take(3).collect(),
+    );
+    assert!(!guardian_set.verify(session, &batch.encode(), &repeated_single_signer));
+
     let signed = SignedBatch { batch, signature };
     let block = provide_batch(signed).await;
 